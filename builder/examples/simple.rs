@@ -1,4 +1,3 @@
-
 use derive_builder::Builder;
 
 #[derive(Builder)]
@@ -19,4 +18,7 @@ fn main() {
         .unwrap();
 
     assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert!(command.env.is_empty());
+    assert_eq!(command.current_dir, "..");
 }
@@ -0,0 +1,540 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput};
+use type_name::type_name;
+
+// Returns `key = value` pairs of the field's `#[builder(...)]` attributes. Keys
+// nested in a list such as `setter(strip_option = false)` are returned as
+// `setter.strip_option`.
+fn get_named_values_from_attr(f: &syn::Field) -> Vec<(syn::MetaList, String, syn::Lit)> {
+    let mut named_vaules = Vec::new();
+
+    for attr in &f.attrs {
+        if attr.path.segments.len() != 1 {
+            continue;
+        }
+
+        let attr_ident = &attr.path.segments[0].ident;
+        if attr_ident != "builder" {
+            continue;
+        }
+
+        // let meta = syn::Meta::parse(&attr.tokens).unwrap();
+        let meta = attr.parse_meta().unwrap();
+
+        if let syn::Meta::List(ref meta_list) = meta {
+            for n in &meta_list.nested {
+                match n {
+                    syn::NestedMeta::Meta(syn::Meta::NameValue(ref nv)) => {
+                        let name = nv.path.segments[0].ident.to_string();
+                        named_vaules.push((meta_list.clone(), name, nv.lit.clone()));
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::List(ref inner)) => {
+                        let group = &inner.path.segments[0].ident;
+                        for n in &inner.nested {
+                            if let syn::NestedMeta::Meta(syn::Meta::NameValue(ref nv)) = n {
+                                let name = format!("{}.{}", group, nv.path.segments[0].ident);
+                                named_vaules.push((meta_list.clone(), name, nv.lit.clone()));
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    named_vaules
+}
+
+fn get_value_of_each(f: &syn::Field) -> Option<String> {
+    for (_, name, value) in get_named_values_from_attr(f) {
+        if name == "each" {
+            if let syn::Lit::Str(ref s) = value {
+                return Some(s.value());
+            }
+        }
+    }
+    None
+}
+
+// `#[builder(setter(strip_option = false))]` makes the setter of an Option
+// field take the Option itself.
+fn get_strip_option(f: &syn::Field) -> syn::Result<bool> {
+    for (_, name, value) in get_named_values_from_attr(f) {
+        if name == "setter.strip_option" {
+            if let syn::Lit::Bool(ref b) = value {
+                return Ok(b.value);
+            }
+            return Err(syn::Error::new_spanned(
+                value,
+                "expected `strip_option = true` or `strip_option = false`",
+            ));
+        }
+    }
+    Ok(true)
+}
+
+// Every item of a field's `#[builder(...)]` must be `each = "..."` or a
+// `setter(...)` list of `strip_option = ...`. Items that
+// `get_named_values_from_attr` doesn't return, like `setter(strip_option)`,
+// are reported here rather than silently ignored.
+fn check_attribute(f: &syn::Field) -> Option<syn::Error> {
+    for attr in &f.attrs {
+        if !attr.path.is_ident("builder") {
+            continue;
+        }
+
+        let meta_list = match attr.parse_meta() {
+            Ok(syn::Meta::List(meta_list)) => meta_list,
+            Ok(meta) => return Some(unrecognized_attribute(&meta)),
+            Err(err) => return Some(err),
+        };
+        let valid = meta_list.nested.iter().all(|n| match n {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(ref nv)) => nv.path.is_ident("each"),
+            syn::NestedMeta::Meta(syn::Meta::List(ref inner)) if inner.path.is_ident("setter") => {
+                inner.nested.iter().all(|n| {
+                    matches!(n, syn::NestedMeta::Meta(syn::Meta::NameValue(ref nv))
+                        if nv.path.is_ident("strip_option"))
+                })
+            }
+            _ => false,
+        });
+        if !valid {
+            return Some(unrecognized_attribute(&meta_list));
+        }
+    }
+    None
+}
+
+fn unrecognized_attribute<T: quote::ToTokens>(tokens: T) -> syn::Error {
+    syn::Error::new_spanned(
+        tokens,
+        "expected `builder(each = \"...\")` or `builder(setter(strip_option = ...))`",
+    )
+}
+
+// Container level `#[builder(...)]` flags.
+fn get_flags_from_attrs(attrs: &[syn::Attribute]) -> syn::Result<Vec<syn::Ident>> {
+    let mut flags = Vec::new();
+
+    for attr in attrs {
+        if !attr.path.is_ident("builder") {
+            continue;
+        }
+
+        let meta = attr.parse_meta()?;
+        if let syn::Meta::List(ref meta_list) = meta {
+            for n in &meta_list.nested {
+                match n {
+                    syn::NestedMeta::Meta(syn::Meta::Path(ref path))
+                        if path.is_ident("no_std") || path.is_ident("no_update") =>
+                    {
+                        flags.push(path.segments[0].ident.clone());
+                    }
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            meta_list,
+                            "expected `builder(no_std)` or `builder(no_update)`",
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    Ok(flags)
+}
+
+fn get_doc_from_attrs(attrs: &[syn::Attribute]) -> String {
+    let mut lines = Vec::new();
+    for attr in attrs {
+        if !attr.path.is_ident("doc") {
+            continue;
+        }
+        if let Ok(syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Str(ref s),
+            ..
+        })) = attr.parse_meta()
+        {
+            lines.push(s.value().trim().to_owned());
+        }
+    }
+    lines.join("\n")
+}
+
+// The names of the `update_<field>` and `<each>_with` methods of `f`.
+fn update_method_names(f: &FieldInfo) -> Vec<syn::Ident> {
+    let mut names = vec![quote::format_ident!(
+        "update_{}",
+        f.field.ident.as_ref().unwrap()
+    )];
+    if f.vec_inner_type.is_some() {
+        names.push(quote::format_ident!(
+            "{}_with",
+            f.each_func.as_ref().unwrap()
+        ));
+    }
+    names
+}
+
+// The update methods are named after the fields, so they may clash with the
+// setters, `build` or each other, e.g. for fields `x` and `update_x`.
+fn check_update_methods(fields_info: &[FieldInfo]) -> Option<syn::Error> {
+    let mut taken: Vec<syn::Ident> = vec![quote::format_ident!("build")];
+    for f in fields_info {
+        match f.each_func {
+            Some(ref each_func) if f.vec_inner_type.is_some() => taken.push(each_func.clone()),
+            _ => taken.push(f.field.ident.clone().unwrap()),
+        }
+    }
+
+    let mut errors: Option<syn::Error> = None;
+    for f in fields_info {
+        for name in update_method_names(f) {
+            if taken.contains(&name) {
+                let err = syn::Error::new_spanned(
+                    f.field.ident.as_ref().unwrap(),
+                    format!(
+                        "the generated method `{}` clashes with another method of the builder, \
+                         use `#[builder(no_update)]` on the struct to leave out the update methods",
+                        name
+                    ),
+                );
+                match errors {
+                    Some(ref mut errors) => errors.combine(err),
+                    None => errors = Some(err),
+                }
+            }
+            taken.push(name);
+        }
+    }
+    errors
+}
+
+struct FieldInfo<'a> {
+    field: &'a syn::Field,
+    option_inner_type: Option<&'a syn::Type>,
+    strip_option: bool,
+
+    each_func: Option<syn::Ident>,
+    vec_inner_type: Option<&'a syn::Type>,
+}
+
+#[proc_macro_derive(Builder, attributes(builder))]
+pub fn derive(input: TokenStream) -> TokenStream {
+    let DeriveInput {
+        ident, data, attrs, ..
+    } = parse_macro_input!(input);
+
+    let flags = match get_flags_from_attrs(&attrs) {
+        Ok(flags) => flags,
+        Err(err) => return err.into_compile_error().into(),
+    };
+
+    // `core`/`alloc` paths are used when the caller is `no_std`, either per
+    // struct or for the whole build with the `std` feature turned off.
+    let no_std = !cfg!(feature = "std") || flags.iter().any(|f| f == "no_std");
+    let (core, alloc) = if no_std {
+        (quote!(::core), quote!(::alloc))
+    } else {
+        (quote!(::std), quote!(::std))
+    };
+
+    let fields = if let syn::Data::Struct(syn::DataStruct {
+        fields: syn::Fields::Named(syn::FieldsNamed { ref named, .. }),
+        ..
+    }) = data
+    {
+        named
+    } else {
+        unimplemented!()
+    };
+
+    for f in fields {
+        if let Some(err) = check_attribute(f) {
+            return err.into_compile_error().into();
+        }
+    }
+
+    let fields_info = fields
+        .iter()
+        .map(|f| -> syn::Result<_> {
+            let mut info = FieldInfo {
+                field: f,
+                option_inner_type: None,
+                strip_option: true,
+                each_func: None,
+                vec_inner_type: None,
+            };
+
+            let strip_option = get_strip_option(f)?;
+            if let Some(inner_type) = unwrap_ty(&f.ty, "Option") {
+                info.option_inner_type = Some(inner_type);
+                info.strip_option = strip_option;
+            } else {
+                if !strip_option {
+                    return Err(syn::Error::new_spanned(
+                        &f.ty,
+                        "`strip_option` only applies to Option fields",
+                    ));
+                }
+
+                if let Some(each_func) = get_value_of_each(f) {
+                    if let Some(inner_type) = unwrap_ty(&f.ty, "Vec") {
+                        info.each_func = Some(quote::format_ident!("{}", each_func));
+                        info.vec_inner_type = Some(inner_type);
+                    }
+                }
+            }
+
+            Ok(info)
+        })
+        .collect::<Vec<_>>();
+
+    let mut errors: Option<syn::Error> = None;
+    for err in fields_info.iter().filter_map(|info| info.as_ref().err()) {
+        match errors {
+            Some(ref mut errors) => errors.combine(err.clone()),
+            None => errors = Some(err.clone()),
+        }
+    }
+    if let Some(errors) = errors {
+        return errors.into_compile_error().into();
+    }
+    let fields_info = fields_info
+        .into_iter()
+        .map(Result::unwrap)
+        .collect::<Vec<_>>();
+
+    let no_update = flags.iter().any(|f| f == "no_update");
+    if !no_update {
+        if let Some(err) = check_update_methods(&fields_info) {
+            return err.into_compile_error().into();
+        }
+    }
+
+    let builder_ident = quote::format_ident!("{}Builder", ident);
+    let error_ident = quote::format_ident!("{}BuilderError", ident);
+
+    let builder_fields = fields_info.iter().map(|f| {
+        let ty = &f.field.ty;
+        let ident = &f.field.ident;
+
+        if f.option_inner_type.is_some() || f.vec_inner_type.is_some() {
+            quote! {
+                #ident: #ty
+            }
+        } else {
+            quote! {
+                #ident: #core::option::Option::<#ty>
+            }
+        }
+    });
+
+    let builder_methods = fields_info.iter().map(|f| {
+        let ty = &f.field.ty;
+        let ident = &f.field.ident;
+        if f.option_inner_type.is_some() && !f.strip_option {
+            quote! {
+                fn #ident(&mut self, #ident: #ty) -> &mut Self {
+                    self.#ident = #ident;
+                    self
+                }
+            }
+        } else if let Some(inner_type) = f.option_inner_type {
+            quote! {
+                fn #ident(&mut self, #ident: #inner_type) -> &mut Self {
+                    self.#ident = #core::option::Option::Some(#ident);
+                    self
+                }
+            }
+        } else {
+            if let Some(inner_type) = f.vec_inner_type {
+                let name = f.each_func.as_ref().unwrap();
+                quote! {
+                    fn #name(&mut self, #ident: #inner_type) -> &mut Self {
+                        self.#ident.push(#ident);
+                        self
+                    }
+                }
+            } else {
+                quote! {
+                    fn #ident(&mut self, #ident: #ty) -> &mut Self {
+                        self.#ident = #core::option::Option::Some(#ident);
+                        self
+                    }
+                }
+            }
+        }
+    });
+
+    // Closure based setters that change a value already set on the builder in
+    // place. Unset fields are left alone. `#[builder(no_update)]` leaves them
+    // out.
+    let update_fields = if no_update { &[][..] } else { &fields_info[..] };
+    let update_methods = update_fields.iter().map(|f| {
+        let ty = &f.field.ty;
+        let ident = &f.field.ident;
+        let names = update_method_names(f);
+        let update = &names[0];
+        if let Some(inner_type) = f.vec_inner_type {
+            let each_with = &names[1];
+            quote! {
+                fn #update(&mut self, f: impl #core::ops::FnOnce(&mut #ty)) -> &mut Self {
+                    f(&mut self.#ident);
+                    self
+                }
+
+                fn #each_with(&mut self, f: impl #core::ops::FnOnce(&mut #inner_type)) -> &mut Self {
+                    if let #core::option::Option::Some(item) = self.#ident.last_mut() {
+                        f(item);
+                    }
+                    self
+                }
+            }
+        } else {
+            let inner_type = f.option_inner_type.unwrap_or(ty);
+            quote! {
+                fn #update(&mut self, f: impl #core::ops::FnOnce(&mut #inner_type)) -> &mut Self {
+                    if let #core::option::Option::Some(value) = self.#ident.as_mut() {
+                        f(value);
+                    }
+                    self
+                }
+            }
+        }
+    });
+
+    let fields_meta = fields_info.iter().map(|f| {
+        let ty = &f.field.ty;
+        let ident = &f.field.ident;
+        let ty_name = type_name(ty);
+        let doc = get_doc_from_attrs(&f.field.attrs);
+        let (kind, each) = if let Some(ref each_func) = f.each_func {
+            (
+                quote! { Each },
+                quote! { #core::option::Option::Some(stringify!(#each_func)) },
+            )
+        } else if f.option_inner_type.is_some() {
+            (quote! { Optional }, quote! { #core::option::Option::None })
+        } else {
+            (quote! { Required }, quote! { #core::option::Option::None })
+        };
+        quote! {
+            ::derive_builder::FieldInfo {
+                name: stringify!(#ident),
+                ty: #ty_name,
+                kind: ::derive_builder::FieldKind::#kind,
+                each: #each,
+                doc: #doc,
+            }
+        }
+    });
+
+    let builder_none = fields_info.iter().map(|f| {
+        let ident = &f.field.ident;
+        if f.each_func.is_some() {
+            quote! {
+                #ident: #alloc::vec::Vec::new()
+            }
+        } else {
+            quote! {
+                #ident: #core::option::Option::None
+            }
+        }
+    });
+
+    let return_fields = fields_info.iter().map(|f| {
+        let ident = &f.field.ident;
+        if f.option_inner_type.is_some() || f.each_func.is_some() {
+            quote! {
+                #ident: self.#ident.clone()
+            }
+        } else {
+            quote! {
+                #ident: self.#ident.clone().ok_or(#error_ident {
+                    field: stringify!(#ident),
+                })?
+            }
+        }
+    });
+
+    let std_error_impl = if no_std {
+        quote! {}
+    } else {
+        quote! {
+            impl ::std::error::Error for #error_ident {}
+        }
+    };
+
+    let output = quote! {
+        #[derive(Debug)]
+        pub struct #error_ident {
+            field: &'static str,
+        }
+
+        impl #core::fmt::Display for #error_ident {
+            fn fmt(&self, f: &mut #core::fmt::Formatter<'_>) -> #core::fmt::Result {
+                #core::write!(f, "{} is not set", self.field)
+            }
+        }
+
+        #std_error_impl
+
+        pub struct #builder_ident {
+            #(#builder_fields,)*
+        }
+
+        impl #builder_ident {
+            pub const FIELDS: &'static [::derive_builder::FieldInfo] = &[#(#fields_meta),*];
+
+            #(#builder_methods)*
+
+            #(#update_methods)*
+
+            fn build(&mut self) -> #core::result::Result<#ident, #error_ident> {
+                #core::result::Result::Ok(#ident {
+                    #(#return_fields),*
+                })
+
+            }
+        }
+        impl #ident {
+            pub fn builder() -> #builder_ident {
+                #builder_ident {
+                    #(#builder_none,)*
+                }
+            }
+        }
+    };
+
+    let x: TokenStream = output.into();
+    // println!("{}", x.to_string());
+    x
+}
+
+fn unwrap_ty<'a>(ty: &'a syn::Type, name: &'_ str) -> Option<&'a syn::Type> {
+    if let syn::Type::Path(syn::TypePath {
+        path: syn::Path { ref segments, .. },
+        ..
+    }) = ty
+    {
+        if segments.len() != 1 || segments[0].ident != name {
+            return None;
+        }
+
+        if let syn::PathArguments::AngleBracketed(syn::AngleBracketedGenericArguments {
+            ref args,
+            ..
+        }) = segments[0].arguments
+        {
+            if args.len() != 1 {
+                panic!("not valid Option type");
+            }
+
+            if let syn::GenericArgument::Type(ref ty) = args[0] {
+                return Some(ty);
+            }
+        }
+    }
+    None
+}
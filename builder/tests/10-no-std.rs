@@ -0,0 +1,44 @@
+// The generated builder should also be usable from `no_std` crates, which only
+// have `core` and `alloc` to work with. Opt in per struct with
+// #[builder(no_std)], or for every struct by turning off the `std` feature of
+// this crate.
+//
+// The error returned by `build` implements core::fmt::Display, and
+// std::error::Error only when std is available.
+//
+// std is still linked here so that this test can run as a normal binary, but
+// under a different name so that the generated code can't refer to `::std`.
+
+#![no_std]
+
+extern crate alloc;
+extern crate std as host_std;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(no_std)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable(String::from("cargo"))
+        .arg(String::from("build"))
+        .arg(String::from("--release"))
+        .build()
+        .unwrap();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, alloc::vec!["build", "--release"]);
+    assert!(command.current_dir.is_none());
+
+    let err = Command::builder().build().err().unwrap();
+    assert_eq!(err.to_string(), "executable is not set");
+}
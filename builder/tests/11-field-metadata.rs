@@ -0,0 +1,81 @@
+// Tools that print `--help` output or a JSON schema for a config struct need
+// to know what the builder expects without constructing one.
+//
+// The generated builder exposes a `FIELDS` constant describing each field:
+// its name, its type as written, whether it is required, optional or set one
+// element at a time with `each`, and its doc comment. Every builder uses the
+// same `derive_builder::FieldInfo` type, so one function can describe them all.
+
+use derive_builder::{Builder, FieldInfo, FieldKind};
+
+type Filter = fn(&str) -> bool;
+
+#[derive(Builder)]
+pub struct Command {
+    /// Program to run.
+    executable: String,
+    /// Arguments passed to the program,
+    /// in order.
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+    exit_code: fn(u8) -> u8,
+    filter: Option<fn(&str) -> bool>,
+    magic: &'static [u8; 4],
+    env: Vec<(&'static str, *const u8)>,
+    other_filter: Filter,
+    fill: Option<fn(&mut [u8]) -> usize>,
+    raw: *const [u8],
+    swap: fn(&mut (u8, u8)),
+    callback: Option<&'static dyn Fn(u8) -> u8>,
+}
+
+#[derive(Builder)]
+pub struct Server {
+    /// Port to listen on.
+    port: u16,
+}
+
+fn required(fields: &[FieldInfo]) -> Vec<&'static str> {
+    fields
+        .iter()
+        .filter(|f| f.kind == FieldKind::Required)
+        .map(|f| f.name)
+        .collect()
+}
+
+fn main() {
+    let fields = CommandBuilder::FIELDS;
+    assert_eq!(fields.len(), 12);
+
+    assert_eq!(fields[0].name, "executable");
+    assert_eq!(fields[0].ty, "String");
+    assert_eq!(fields[0].kind, FieldKind::Required);
+    assert_eq!(fields[0].each, None);
+    assert_eq!(fields[0].doc, "Program to run.");
+
+    assert_eq!(fields[1].name, "args");
+    assert_eq!(fields[1].ty, "Vec<String>");
+    assert_eq!(fields[1].kind, FieldKind::Each);
+    assert_eq!(fields[1].each, Some("arg"));
+    assert_eq!(fields[1].doc, "Arguments passed to the program,\nin order.");
+
+    assert_eq!(fields[2].name, "current_dir");
+    assert_eq!(fields[2].ty, "Option<String>");
+    assert_eq!(fields[2].kind, FieldKind::Optional);
+    assert_eq!(fields[2].doc, "");
+
+    assert_eq!(fields[3].ty, "fn(u8) -> u8");
+    assert_eq!(fields[4].ty, "Option<fn(&str) -> bool>");
+    assert_eq!(fields[4].kind, FieldKind::Optional);
+    assert_eq!(fields[5].ty, "&'static [u8; 4]");
+    assert_eq!(fields[6].ty, "Vec<(&'static str, *const u8)>");
+    assert_eq!(fields[7].ty, "Filter");
+    assert_eq!(fields[8].ty, "Option<fn(&mut [u8]) -> usize>");
+    assert_eq!(fields[9].ty, "*const [u8]");
+    assert_eq!(fields[10].ty, "fn(&mut (u8, u8))");
+    assert_eq!(fields[11].ty, "Option<&'static dyn Fn(u8) -> u8>");
+
+    assert_eq!(required(ServerBuilder::FIELDS), ["port"]);
+    assert_eq!(required(CommandBuilder::FIELDS)[..2], ["executable", "exit_code"]);
+}
@@ -0,0 +1,58 @@
+// Sometimes a value is set on the builder first and tweaked later, for example
+// appending to a string or bumping a number, without reading it back out.
+//
+// Every field gets an `update_<field>` method taking a closure that receives a
+// mutable reference to the value already set. For Option fields the closure
+// sees the inner value. Fields that are not set yet are left untouched.
+//
+// Fields with #[builder(each = "...")] also get an `<each>_with` method which
+// changes the element most recently added with the one-at-a-time setter.
+//
+// #[builder(no_update)] on the struct leaves these methods out, for structs
+// whose own field names would clash with them.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    jobs: u32,
+    current_dir: Option<String>,
+}
+
+#[derive(Builder)]
+#[builder(no_update)]
+pub struct Job {
+    name: String,
+    update_name: String,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .update_executable(|exe| exe.push_str("-nightly"))
+        .arg("build".to_owned())
+        .arg("--jobs".to_owned())
+        .arg_with(|arg| arg.push_str("=4"))
+        .update_args(|args| args.push("--release".to_owned()))
+        .update_jobs(|jobs| *jobs += 1)
+        .jobs(1)
+        .update_jobs(|jobs| *jobs += 1)
+        .update_current_dir(|dir| dir.push_str("/never"))
+        .build()
+        .unwrap();
+
+    assert_eq!(command.executable, "cargo-nightly");
+    assert_eq!(command.args, vec!["build", "--jobs=4", "--release"]);
+    assert_eq!(command.jobs, 2);
+    assert!(command.current_dir.is_none());
+
+    let job = Job::builder()
+        .name("build".to_owned())
+        .update_name("rebuild".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(job.update_name, "rebuild");
+}
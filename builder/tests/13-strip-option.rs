@@ -0,0 +1,34 @@
+// Setters of Option fields take the inner type by default, so a caller can
+// neither reset the field to None nor forward an Option it already has.
+//
+// With #[builder(setter(strip_option = false))] the setter takes the Option
+// itself.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(setter(strip_option = false))]
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let dir = std::env::args().nth(100);
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .current_dir(Some("..".to_owned()))
+        .current_dir(dir)
+        .build()
+        .unwrap();
+
+    assert!(command.current_dir.is_none());
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .current_dir(Some("..".to_owned()))
+        .build()
+        .unwrap();
+
+    assert_eq!(command.current_dir.as_deref(), Some(".."));
+}
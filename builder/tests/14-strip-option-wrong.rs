@@ -0,0 +1,13 @@
+// `strip_option` only makes sense on Option fields, and takes a bool.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(setter(strip_option = false))]
+    executable: String,
+    #[builder(setter(strip_option = "no"))]
+    current_dir: Option<String>,
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+// The update methods are named after the fields, so a field named like the
+// update method of another field would define the same method twice. That is
+// reported at the field generating the clashing method, and
+// #[builder(no_update)] on the struct leaves the update methods out.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    update_executable: String,
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+// Everything inside `setter(...)` is checked too, a flag without a value or a
+// misspelled option is an error instead of being silently ignored.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(setter(strip_option))]
+    current_dir: Option<String>,
+}
+
+#[derive(Builder)]
+pub struct Job {
+    #[builder(setter(strip_optoin = false))]
+    name: Option<String>,
+}
+
+fn main() {}
@@ -10,4 +10,11 @@ fn tests() {
     t.pass("tests/07-repeated-field.rs");
     t.compile_fail("tests/08-unrecognized-attribute.rs");
     t.pass("tests/09-redefined-prelude-types.rs");
+    t.pass("tests/10-no-std.rs");
+    t.pass("tests/11-field-metadata.rs");
+    t.pass("tests/12-update-in-place.rs");
+    t.pass("tests/13-strip-option.rs");
+    t.compile_fail("tests/14-strip-option-wrong.rs");
+    t.compile_fail("tests/15-update-name-clash.rs");
+    t.compile_fail("tests/16-setter-unrecognized.rs");
 }
@@ -0,0 +1,147 @@
+// #[derive(DebugDiff)] compares two values field by field, by what each field
+// prints. The field attributes of CustomDebug apply, so skipped fields are left
+// out and redacted ones only differ if their redacted output does. A struct or
+// variant with a format string, a union, and two different variants of an enum
+// are compared as a whole instead.
+//
+// Malformed attributes are ignored here, CustomDebug reports them.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{
+    display_name, extend_where_clause, gen_debug_bounds, gen_field_value, gen_member_values,
+    gen_packed_asserts, gen_self_values, gen_variant_pattern, get_rename_all, get_union_as,
+    has_flag_in_attrs, is_flattened, is_packed, is_skipped, FieldsOptions,
+};
+
+pub(crate) fn derive(input: &syn::DeriveInput) -> TokenStream {
+    let ident = &input.ident;
+    let options = FieldsOptions::from_attrs(&input.attrs);
+    let packed = is_packed(&input.attrs);
+
+    let (fields, body): (Vec<&syn::Field>, TokenStream) = match input.data {
+        syn::Data::Struct(syn::DataStruct { ref fields, .. }) if options.format.is_none() => {
+            let lefts = gen_self_values(fields, packed);
+            let rights = gen_member_values(&quote! { __other }, fields, packed);
+            // A transparent struct is compared as its only field.
+            let transparent = has_flag_in_attrs(&input.attrs, "transparent");
+            let len = if transparent { 1 } else { fields.len() };
+            let fields: Vec<_> = fields.iter().take(len).collect();
+            let body = gen_diff_fields(&fields, &lefts, &rights, transparent, &options);
+            (fields, body)
+        }
+        syn::Data::Enum(ref data) if data.variants.is_empty() => {
+            (Vec::new(), quote! { match *self {} })
+        }
+        syn::Data::Enum(ref data) => {
+            let arms = data
+                .variants
+                .iter()
+                .filter(|v| FieldsOptions::from_attrs(&v.attrs).format.is_none())
+                .map(|v| {
+                    let lefts = gen_bindings("__self", v.fields.len());
+                    let rights = gen_bindings("__other", v.fields.len());
+                    let variant_options = FieldsOptions {
+                        rename_all: get_rename_all(&v.attrs),
+                        skip_none: false,
+                        format: None,
+                    };
+                    let fields: Vec<_> = v.fields.iter().collect();
+                    let body = gen_diff_fields(&fields, &lefts, &rights, false, &variant_options);
+                    let left = gen_variant_pattern(v, &lefts, &variant_options);
+                    let right = gen_variant_pattern(v, &rights, &variant_options);
+                    quote! { (#left, #right) => #body }
+                });
+            let body = quote! {
+                #[allow(unreachable_patterns)]
+                match (self, __other) {
+                    #(#arms)*
+                    _ => __diff.compare("", self, __other),
+                }
+            };
+            let fields = data.variants.iter().flat_map(|v| v.fields.iter()).collect();
+            (fields, body)
+        }
+        _ => (Vec::new(), quote! { __diff.compare("", self, __other); }),
+    };
+    let packed_asserts = if packed {
+        gen_packed_asserts(fields.iter().copied(), &options)
+    } else {
+        TokenStream::new()
+    };
+
+    // Nested values are printed with their Debug impl when they don't derive
+    // DebugDiff themselves, so the type needs Debug bounds as if it was being
+    // printed, and to implement Debug as DebugDiff requires.
+    let union_field = match input.data {
+        syn::Data::Union(ref data) => get_union_as(&input.attrs, data).and_then(Result::ok),
+        _ => None,
+    };
+    let mut generics = gen_debug_bounds(
+        input,
+        union_field,
+        &quote! { ::derive_debug::diff::DebugDiff },
+    );
+    let (_, type_generics, _) = input.generics.split_for_impl();
+    extend_where_clause(
+        &mut generics,
+        quote! { #ident #type_generics: ::core::fmt::Debug },
+    );
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::derive_debug::diff::DebugDiff for #ident #type_generics #where_clause {
+            fn debug_diff(&self, __other: &Self) -> ::derive_debug::diff::Diff {
+                #[allow(unused_imports)]
+                use ::derive_debug::diff::{ViaDebug as _, ViaDebugDiff as _};
+                let mut __diff = ::derive_debug::diff::Diff::new();
+                #packed_asserts
+                #body
+                __diff
+            }
+        }
+    }
+}
+
+fn gen_bindings(prefix: &str, len: usize) -> Vec<TokenStream> {
+    (0..len)
+        .map(|i| {
+            let binding = quote::format_ident!("{}_{}", prefix, i);
+            quote! { #binding }
+        })
+        .collect()
+}
+
+// Compares each of the `fields` that would be printed, `lefts` and `rights`
+// are references to them in `self` and `__other`. Fields are named like in
+// `gen_visit_fields`, flattened ones and the field of a transparent struct
+// add their changes without a name of their own.
+fn gen_diff_fields(
+    fields: &[&syn::Field],
+    lefts: &[TokenStream],
+    rights: &[TokenStream],
+    transparent: bool,
+    options: &FieldsOptions,
+) -> TokenStream {
+    let diff_fields = fields
+        .iter()
+        .zip(lefts.iter().zip(rights))
+        .enumerate()
+        .filter(|(_, (f, _))| !is_skipped(f))
+        .map(|(i, (f, (left, right)))| {
+            let path = match f.ident {
+                _ if transparent || is_flattened(f) => String::new(),
+                Some(ref ident) => display_name(ident, &f.attrs, options.rename_all.as_deref()),
+                None => i.to_string(),
+            };
+            let left = gen_field_value(f, left);
+            let right = gen_field_value(f, right);
+            quote! {
+                (&::derive_debug::diff::DiffField(#left, #right)).diff_into(#path, &mut __diff);
+            }
+        });
+    quote! {{
+        #(#diff_fields)*
+    }}
+}
@@ -0,0 +1,342 @@
+// #[derive(CustomDisplay)] formats a struct, or each enum variant, with the
+// format string in its `#[display("...")]` attribute. Placeholders name the
+// fields, `{name}` for named fields and `{0}` for tuple fields, and may carry
+// any format spec such as `{id:08x}`. Every placeholder is rewritten to refer
+// to a `__self_N` argument so that raw identifiers and repeated uses of the
+// same field work. A width or precision taken from a field, `{x:>w$}`, is
+// rewritten to a `__count_N` argument the same way.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+
+use crate::{extend_where_clause, gen_type_constrains2, parse_bound_from_attrs};
+
+fn get_display_lit(attrs: &[syn::Attribute]) -> Option<syn::LitStr> {
+    for attr in attrs {
+        if !attr.path.is_ident("display") {
+            continue;
+        }
+        if let Ok(syn::Meta::List(syn::MetaList { ref nested, .. })) = attr.parse_meta() {
+            for nested in nested.iter() {
+                if let syn::NestedMeta::Lit(syn::Lit::Str(ref lit)) = nested {
+                    return Some(lit.clone());
+                }
+            }
+        }
+    }
+    None
+}
+
+pub(crate) struct FormatString {
+    lit: syn::LitStr,
+    // Index of every field used by a placeholder and the formatting trait it
+    // is used with. Extra arguments are numbered after the fields.
+    pub(crate) used: Vec<(usize, TokenStream)>,
+    // Index of every field used as a width or precision.
+    pub(crate) counts: Vec<usize>,
+}
+
+// Placeholders may also name one of the extra `args`, which the caller passes
+// after the field values to `gen_write`.
+pub(crate) fn parse_format(
+    lit: &syn::LitStr,
+    fields: &syn::Fields,
+    args: &[&syn::Ident],
+) -> syn::Result<FormatString> {
+    let value = lit.value();
+    let mut format = String::new();
+    let mut used = Vec::new();
+    let mut counts = Vec::new();
+
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                format.push_str("{{");
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                format.push_str("}}");
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => {
+                            return Err(syn::Error::new(
+                                lit.span(),
+                                "unterminated `{` in format string",
+                            ))
+                        }
+                    }
+                }
+                let (arg, spec) = match placeholder.find(':') {
+                    Some(i) => placeholder.split_at(i),
+                    None => (placeholder.as_str(), ""),
+                };
+                let index = find_field(fields, args, arg.trim(), lit)?;
+                let spec = parse_counts(spec, fields, args, lit, &mut counts)?;
+                format.push_str(&format!("{{__self_{}{}}}", index, spec));
+                used.push((index, format_trait(&spec)));
+            }
+            c => format.push(c),
+        }
+    }
+
+    Ok(FormatString {
+        lit: syn::LitStr::new(&format, lit.span()),
+        used,
+        counts,
+    })
+}
+
+// Rewrites every `name$` width or precision in `spec` to `__count_N$`. The
+// `.*` precision takes the next positional argument, which doesn't exist once
+// placeholders are named.
+fn parse_counts(
+    spec: &str,
+    fields: &syn::Fields,
+    args: &[&syn::Ident],
+    lit: &syn::LitStr,
+    counts: &mut Vec<usize>,
+) -> syn::Result<String> {
+    if spec.contains(".*") {
+        return Err(syn::Error::new(
+            lit.span(),
+            "`.*` is not supported, name the precision like `{value:.prec$}`",
+        ));
+    }
+
+    let mut rewritten = String::new();
+    let mut name = String::new();
+    for c in spec.chars() {
+        if c.is_alphanumeric() || c == '_' {
+            name.push(c);
+            continue;
+        }
+        if c == '$' && !name.is_empty() {
+            let index = find_field(fields, args, &name, lit)?;
+            rewritten.push_str(&format!("__count_{}", index));
+            counts.push(index);
+        } else {
+            rewritten.push_str(&name);
+        }
+        name.clear();
+        rewritten.push(c);
+    }
+    rewritten.push_str(&name);
+    Ok(rewritten)
+}
+
+fn find_field(
+    fields: &syn::Fields,
+    args: &[&syn::Ident],
+    arg: &str,
+    lit: &syn::LitStr,
+) -> syn::Result<usize> {
+    if arg.is_empty() {
+        return Err(syn::Error::new(
+            lit.span(),
+            "placeholders must name a field, like `{name}` or `{0}`",
+        ));
+    }
+
+    let found = fields.iter().enumerate().position(|(i, f)| match f.ident {
+        Some(ref ident) => ident.unraw() == arg,
+        None => arg.parse() == Ok(i),
+    });
+    let found = found.or_else(|| {
+        let i = args.iter().position(|ident| ident.unraw() == arg)?;
+        Some(fields.len() + i)
+    });
+    found.ok_or_else(|| syn::Error::new(lit.span(), format!("no field `{}`", arg)))
+}
+
+// The trait a placeholder's spec formats with, `:x` is LowerHex, `:?` Debug.
+fn format_trait(spec: &str) -> TokenStream {
+    let name = if spec.ends_with('?') {
+        "Debug"
+    } else {
+        match spec.chars().last() {
+            Some('x') => "LowerHex",
+            Some('X') => "UpperHex",
+            Some('o') => "Octal",
+            Some('b') => "Binary",
+            Some('e') => "LowerExp",
+            Some('E') => "UpperExp",
+            _ => "Display",
+        }
+    };
+    let name = format_ident!("{}", name);
+    quote! { ::core::fmt::#name }
+}
+
+pub(crate) fn gen_write(format: &FormatString, values: &[TokenStream]) -> TokenStream {
+    let lit = &format.lit;
+    let mut indexes: Vec<_> = format.used.iter().map(|(i, _)| *i).collect();
+    indexes.sort_unstable();
+    indexes.dedup();
+    let args = indexes.iter().map(|i| {
+        let name = format_ident!("__self_{}", i);
+        let value = &values[*i];
+        quote! { #name = #value }
+    });
+    let mut counts = format.counts.clone();
+    counts.sort_unstable();
+    counts.dedup();
+    // Counts must be a `usize`, the values may be references to one.
+    let counts = counts.iter().map(|i| {
+        let name = format_ident!("__count_{}", i);
+        let value = &values[*i];
+        quote! { #name = *::core::borrow::Borrow::<usize>::borrow(&#value) }
+    });
+    quote! {
+        __formatter.write_fmt(::core::format_args!(#lit, #(#args,)* #(#counts),*))
+    }
+}
+
+// Bounds the type parameters in each of the `used_fields` on the trait it is
+// formatted with.
+pub(crate) fn gen_trait_constrains(
+    generics: &syn::Generics,
+    used_fields: &[(&syn::Field, TokenStream)],
+) -> TokenStream {
+    let mut traits: Vec<&TokenStream> = Vec::new();
+    for (_, t) in used_fields.iter() {
+        if !traits.iter().any(|u| u.to_string() == t.to_string()) {
+            traits.push(t);
+        }
+    }
+
+    let mut constrains = TokenStream::new();
+    for trait_path in traits {
+        let fields = used_fields
+            .iter()
+            .filter(|(_, t)| t.to_string() == trait_path.to_string())
+            .map(|(f, _)| *f);
+        constrains.extend(gen_type_constrains2(generics, fields, trait_path));
+    }
+    constrains
+}
+
+pub fn derive(input: &syn::DeriveInput) -> syn::Result<TokenStream> {
+    let ident = &input.ident;
+
+    // Fields used by the format strings along with the trait they need.
+    let mut used_fields: Vec<(&syn::Field, TokenStream)> = Vec::new();
+
+    let body = match input.data {
+        syn::Data::Struct(syn::DataStruct { ref fields, .. }) => {
+            let lit = get_display_lit(&input.attrs).ok_or_else(|| {
+                syn::Error::new_spanned(ident, "missing `#[display(\"...\")]` attribute")
+            })?;
+            let format = parse_format(&lit, fields, &[])?;
+            let values: Vec<_> = fields
+                .iter()
+                .enumerate()
+                .map(|(i, f)| match f.ident {
+                    Some(ref ident) => quote! { &self.#ident },
+                    None => {
+                        let index = syn::Index::from(i);
+                        quote! { &self.#index }
+                    }
+                })
+                .collect();
+            let fields: Vec<_> = fields.iter().collect();
+            used_fields.extend(format.used.iter().map(|(i, t)| (fields[*i], t.clone())));
+            gen_write(&format, &values)
+        }
+        syn::Data::Enum(ref data) => {
+            let mut arms = Vec::new();
+            for v in data.variants.iter() {
+                let variant = &v.ident;
+                let format = match get_display_lit(&v.attrs) {
+                    Some(lit) => parse_format(&lit, &v.fields, &[])?,
+                    None if v.fields.is_empty() => {
+                        let name = variant.unraw().to_string();
+                        arms.push(
+                            quote! { Self::#variant { .. } => __formatter.write_str(#name), },
+                        );
+                        continue;
+                    }
+                    None => {
+                        return Err(syn::Error::new_spanned(
+                            v,
+                            "missing `#[display(\"...\")]` attribute",
+                        ))
+                    }
+                };
+
+                let values: Vec<_> = (0..v.fields.len())
+                    .map(|i| {
+                        let binding = format_ident!("__self_{}", i);
+                        quote! { #binding }
+                    })
+                    .collect();
+                let bindings = values.iter().enumerate().map(|(i, value)| {
+                    if format.used.iter().any(|(used, _)| *used == i) || format.counts.contains(&i)
+                    {
+                        value.clone()
+                    } else {
+                        quote! { _ }
+                    }
+                });
+                let pattern = match v.fields {
+                    syn::Fields::Named(syn::FieldsNamed { ref named, .. }) => {
+                        let names = named.iter().map(|f| &f.ident);
+                        quote! { Self::#variant { #(#names: #bindings),* } }
+                    }
+                    syn::Fields::Unnamed(_) => quote! { Self::#variant(#(#bindings),*) },
+                    syn::Fields::Unit => quote! { Self::#variant },
+                };
+
+                let fields: Vec<_> = v.fields.iter().collect();
+                used_fields.extend(format.used.iter().map(|(i, t)| (fields[*i], t.clone())));
+                let body = gen_write(&format, &values);
+                arms.push(quote! { #pattern => #body, });
+            }
+
+            if arms.is_empty() {
+                quote! { match *self {} }
+            } else {
+                quote! {
+                    match self {
+                        #(#arms)*
+                    }
+                }
+            }
+        }
+        syn::Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "`CustomDisplay` does not support unions",
+            ))
+        }
+    };
+
+    let mut generics = input.generics.clone();
+    if !generics.params.is_empty() {
+        let constrains = if let Some(bound) = parse_bound_from_attrs(&input.attrs, "display") {
+            let predicates = bound.parse_with(
+                syn::punctuated::Punctuated::<syn::WherePredicate, syn::Token![,]>::parse_terminated,
+            )?;
+            quote! { #predicates }
+        } else {
+            gen_trait_constrains(&input.generics, &used_fields)
+        };
+        extend_where_clause(&mut generics, constrains);
+    }
+
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::core::fmt::Display for #ident #type_generics #where_clause {
+            fn fmt(&self, __formatter: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                #body
+            }
+        }
+    })
+}
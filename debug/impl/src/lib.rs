@@ -0,0 +1,1676 @@
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned, ToTokens};
+use syn::ext::IdentExt;
+use syn::parse::Parser;
+use syn::spanned::Spanned;
+use type_name::type_name;
+
+mod diff;
+mod display;
+
+// `#[debug = "..."]` on a field.
+fn get_custom_fmt(f: &syn::Field) -> Option<syn::Lit> {
+    for attr in f.attrs.iter() {
+        if !attr.path.is_ident("debug") {
+            continue;
+        }
+        if let Ok(syn::Meta::NameValue(nv)) = attr.parse_meta() {
+            return Some(nv.lit);
+        }
+    }
+
+    None
+}
+
+// Everything inside the `#[attr_name(...)]` attributes. Attributes that don't
+// parse are skipped here, `check_debug_attrs` reports them.
+fn nested_metas(attrs: &[syn::Attribute], attr_name: &str) -> Vec<syn::NestedMeta> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident(attr_name))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(syn::Meta::List(syn::MetaList { nested, .. })) => Some(nested),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+// `bound = "..."` in the `#[debug(...)]` or `#[display(...)]` attributes.
+fn parse_bound_from_attrs(attrs: &[syn::Attribute], attr_name: &str) -> Option<syn::LitStr> {
+    nested_metas(attrs, attr_name)
+        .into_iter()
+        .find_map(|nested| match nested {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                path,
+                lit: syn::Lit::Str(lit),
+                ..
+            })) if path.is_ident("bound") => Some(lit),
+            _ => None,
+        })
+}
+
+// Finds `name = "..."` in the `#[debug(...)]` attributes.
+fn get_lit_from_attrs(attrs: &[syn::Attribute], name: &str) -> Option<syn::Lit> {
+    nested_metas(attrs, "debug")
+        .into_iter()
+        .find_map(|nested| match nested {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path.is_ident(name) => {
+                Some(nv.lit)
+            }
+            _ => None,
+        })
+}
+
+// The keys accepted in `#[debug(...)]` on each kind of item. An enum takes
+// `fmt` only to report that format strings go on its variants.
+const STRUCT_KEYS: &[&str] = &[
+    "bound",
+    "rename",
+    "rename_all",
+    "skip_none",
+    "transparent",
+    "fields",
+    "max_depth",
+    "fmt",
+    "args",
+];
+const ENUM_KEYS: &[&str] = &[
+    "bound",
+    "rename_all",
+    "skip_none",
+    "fields",
+    "max_depth",
+    "fmt",
+    "args",
+];
+const UNION_KEYS: &[&str] = &[
+    "bound",
+    "rename",
+    "rename_all",
+    "fields",
+    "max_depth",
+    "unsafe_union_as",
+];
+const VARIANT_KEYS: &[&str] = &["rename", "rename_all", "skip_none", "fmt", "args"];
+const FIELD_KEYS: &[&str] = &[
+    "skip",
+    "redact",
+    "with",
+    "bound",
+    "rename",
+    "skip_if",
+    "truncate",
+    "len_only",
+    "flatten",
+    "ref_id",
+    "phantom",
+    "no_bound",
+    "hex",
+    "bin",
+    "octal",
+    "hex_bytes",
+    "bitflags",
+];
+
+// Keys written as a bare flag, like `skip`, and as `key = value`. `redact` is
+// both, and `args(...)` is the only list.
+const FLAG_KEYS: &[&str] = &[
+    "skip",
+    "redact",
+    "len_only",
+    "flatten",
+    "ref_id",
+    "phantom",
+    "no_bound",
+    "hex",
+    "bin",
+    "octal",
+    "hex_bytes",
+    "transparent",
+    "skip_none",
+    "fields",
+];
+const VALUE_KEYS: &[&str] = &[
+    "with",
+    "bound",
+    "rename",
+    "rename_all",
+    "skip_if",
+    "truncate",
+    "max_depth",
+    "unsafe_union_as",
+    "fmt",
+    "redact",
+    "bitflags",
+];
+
+// Reports `#[debug(...)]` attributes that don't parse, keys not in `keys` and
+// keys written in the wrong form. `#[debug = "..."]` is only allowed on fields.
+fn check_debug_attrs(attrs: &[syn::Attribute], keys: &[&str], errors: &mut Vec<syn::Error>) {
+    let is_field = keys == FIELD_KEYS;
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("debug")) {
+        let nested = match attr.parse_meta() {
+            Ok(syn::Meta::List(syn::MetaList { nested, .. })) => nested,
+            Ok(syn::Meta::NameValue(nv)) if is_field => {
+                if let Err(err) = check_custom_fmt(&nv.lit) {
+                    errors.push(err);
+                }
+                continue;
+            }
+            Ok(syn::Meta::NameValue(nv)) => {
+                errors.push(syn::Error::new_spanned(
+                    nv,
+                    "`#[debug = \"...\"]` is only supported on fields",
+                ));
+                continue;
+            }
+            Ok(syn::Meta::Path(path)) => {
+                errors.push(syn::Error::new_spanned(path, "expected `debug(...)`"));
+                continue;
+            }
+            Err(err) => {
+                errors.push(err);
+                continue;
+            }
+        };
+
+        for nested in nested {
+            let meta = match nested {
+                syn::NestedMeta::Lit(syn::Lit::Str(_)) if keys.contains(&"fmt") => continue,
+                syn::NestedMeta::Lit(lit) => {
+                    errors.push(syn::Error::new_spanned(
+                        lit,
+                        "expected `key` or `key = ...`",
+                    ));
+                    continue;
+                }
+                syn::NestedMeta::Meta(meta) => meta,
+            };
+
+            let name = meta.path().to_token_stream().to_string();
+            if !keys.contains(&name.as_str()) {
+                errors.push(syn::Error::new_spanned(
+                    meta.path(),
+                    format!(
+                        "unknown `debug` attribute `{}`, expected one of {}",
+                        name,
+                        keys.join(", "),
+                    ),
+                ));
+                continue;
+            }
+            let message = match meta {
+                syn::Meta::Path(_) if !FLAG_KEYS.contains(&name.as_str()) => {
+                    format!("expected `{} = ...`", name)
+                }
+                syn::Meta::NameValue(_) if !VALUE_KEYS.contains(&name.as_str()) => {
+                    format!("`{}` doesn't take a value", name)
+                }
+                syn::Meta::List(_) if name != "args" => format!("expected `{}`", name),
+                syn::Meta::Path(_) if name == "args" => format!("expected `{}(...)`", name),
+                _ => continue,
+            };
+            errors.push(syn::Error::new_spanned(meta, message));
+        }
+    }
+}
+
+// `#[debug = "..."]` is passed only the field, so every placeholder has to
+// refer to that one argument, as `{}` or `{0}` with any format spec.
+fn check_custom_fmt(lit: &syn::Lit) -> syn::Result<()> {
+    let lit = match lit {
+        syn::Lit::Str(lit) => lit,
+        lit => {
+            return Err(syn::Error::new_spanned(
+                lit,
+                "expected `#[debug = \"...\"]`",
+            ))
+        }
+    };
+
+    let value = lit.value();
+    let mut placeholders = 0;
+    // Each `{}` takes the next argument, so only one of them is allowed.
+    let mut implicit = 0;
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+            }
+            '{' => {
+                let placeholder: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                let (arg, spec) = match placeholder.find(':') {
+                    Some(i) => placeholder.split_at(i),
+                    None => (placeholder.as_str(), ""),
+                };
+                if !matches!(arg.trim(), "" | "0") || spec.contains('$') || spec.contains(".*") {
+                    return Err(syn::Error::new(
+                        lit.span(),
+                        format!(
+                            "`{{{}}}` refers to another argument, the format string is only \
+                             given the field, use `{{}}` or `{{0}}`",
+                            placeholder,
+                        ),
+                    ));
+                }
+                placeholders += 1;
+                if arg.trim().is_empty() {
+                    implicit += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if implicit > 1 {
+        return Err(syn::Error::new(
+            lit.span(),
+            format!(
+                "format string takes {} arguments but is only given the field, \
+                 use `{{0}}` to print it more than once",
+                implicit,
+            ),
+        ));
+    }
+    if placeholders == 0 {
+        return Err(syn::Error::new(
+            lit.span(),
+            "format string has no placeholder for the field, like `{}` or `{:?}`",
+        ));
+    }
+    Ok(())
+}
+
+fn is_skipped(f: &syn::Field) -> bool {
+    has_flag_in_attrs(&f.attrs, "skip")
+}
+
+// `#[debug(redact)]` or `#[debug(redact = "len")]`.
+fn is_redacted(f: &syn::Field) -> bool {
+    has_flag_in_attrs(&f.attrs, "redact") || get_lit_from_attrs(&f.attrs, "redact").is_some()
+}
+
+fn check_field_attrs(f: &syn::Field, errors: &mut Vec<syn::Error>) {
+    check_debug_attrs(&f.attrs, FIELD_KEYS, errors);
+    if let Some(Err(err)) = get_with_fn(f) {
+        errors.push(err);
+    }
+    if let Some(Err(err)) = get_bound(&f.attrs) {
+        errors.push(err);
+    }
+    if let Some(Err(err)) = get_skip_if(f) {
+        errors.push(err);
+    }
+    if let Some(Err(err)) = get_truncate(f) {
+        errors.push(err);
+    }
+    if let Some(Err(err)) = get_bitflags(f) {
+        errors.push(err);
+    }
+    let presets = NUMERIC_PRESETS
+        .iter()
+        .filter(|preset| has_flag_in_attrs(&f.attrs, preset))
+        .count()
+        + get_bitflags(f).is_some() as usize;
+    if presets > 1 {
+        errors.push(syn::Error::new_spanned(
+            f,
+            "only one of `hex`, `bin`, `octal`, `hex_bytes` and `bitflags` can be used on a field",
+        ));
+    }
+
+    match get_lit_from_attrs(&f.attrs, "redact") {
+        Some(syn::Lit::Str(ref s)) if s.value() == "len" => {}
+        Some(lit) => errors.push(syn::Error::new_spanned(
+            lit,
+            "expected `debug(redact)` or `debug(redact = \"len\")`",
+        )),
+        None => {}
+    }
+}
+
+// `#[debug(bound = "...")]` on the container replaces all inferred bounds, on
+// a field only the bounds inferred from that field. An empty string means no
+// bound at all.
+fn get_bound(
+    attrs: &[syn::Attribute],
+) -> Option<syn::Result<syn::punctuated::Punctuated<syn::WherePredicate, syn::Token![,]>>> {
+    let bound = parse_bound_from_attrs(attrs, "debug")?;
+    Some(bound.parse_with(syn::punctuated::Punctuated::parse_terminated))
+}
+
+const BUILTIN_FORMATTERS: &[&str] = &["hex_bytes", "short_str", "human_duration"];
+
+// `#[debug(with = "path::to::fn")]`, bare names of the formatters shipped in
+// `derive_debug::fmt` refer to those.
+fn get_with_fn(f: &syn::Field) -> Option<syn::Result<TokenStream>> {
+    let lit = match get_lit_from_attrs(&f.attrs, "with")? {
+        syn::Lit::Str(lit) => lit,
+        lit => {
+            return Some(Err(syn::Error::new_spanned(
+                lit,
+                "expected `debug(with = \"path::to::fn\")`",
+            )))
+        }
+    };
+
+    Some(lit.parse::<syn::Path>().map(|path| match path.get_ident() {
+        Some(ident) if BUILTIN_FORMATTERS.iter().any(|name| ident == name) => {
+            quote! { ::derive_debug::fmt::#ident }
+        }
+        _ => quote! { #path },
+    }))
+}
+
+// `#[debug(truncate = N)]` prints only the first N elements of a collection,
+// string or byte slice.
+fn get_truncate(f: &syn::Field) -> Option<syn::Result<usize>> {
+    match get_lit_from_attrs(&f.attrs, "truncate")? {
+        syn::Lit::Int(lit) => Some(lit.base10_parse()),
+        lit => Some(Err(syn::Error::new_spanned(
+            lit,
+            "expected `debug(truncate = N)`",
+        ))),
+    }
+}
+
+const NUMERIC_PRESETS: &[&str] = &["hex", "bin", "octal", "hex_bytes"];
+
+// `#[debug(hex)]`, `#[debug(bin)]` and `#[debug(octal)]` print an integer, or
+// every integer in a collection, in that base.
+fn get_radix(f: &syn::Field) -> Option<syn::Ident> {
+    let radix = if has_flag_in_attrs(&f.attrs, "hex") {
+        "Hex"
+    } else if has_flag_in_attrs(&f.attrs, "bin") {
+        "Bin"
+    } else if has_flag_in_attrs(&f.attrs, "octal") {
+        "Octal"
+    } else {
+        return None;
+    };
+    Some(syn::Ident::new(radix, proc_macro2::Span::call_site()))
+}
+
+// `#[debug(bitflags = "Flags")]` prints an integer field as the flags type it
+// holds the bits of, through the `from_bits_truncate` constructor generated by
+// the `bitflags` crate.
+fn get_bitflags(f: &syn::Field) -> Option<syn::Result<syn::Type>> {
+    match get_lit_from_attrs(&f.attrs, "bitflags")? {
+        syn::Lit::Str(lit) => Some(lit.parse()),
+        lit => Some(Err(syn::Error::new_spanned(
+            lit,
+            "expected `debug(bitflags = \"FlagsType\")`",
+        ))),
+    }
+}
+
+// `#[debug(max_depth = N)]` on the container prints `…` in place of values
+// of the type nested more than N levels deep in each other.
+fn get_max_depth(attrs: &[syn::Attribute]) -> Option<syn::Result<usize>> {
+    match get_lit_from_attrs(attrs, "max_depth")? {
+        syn::Lit::Int(lit) => Some(lit.base10_parse()),
+        lit => Some(Err(syn::Error::new_spanned(
+            lit,
+            "expected `debug(max_depth = N)`",
+        ))),
+    }
+}
+
+// Whether the generated code looks at the field's value at all, a plain
+// `#[debug(redact)]` doesn't even need its length. Fields that may be left out
+// are read by their skip condition, see `gen_skip_if`.
+fn reads_value(f: &syn::Field, options: &FieldsOptions) -> bool {
+    if is_skipped(f) {
+        return false;
+    }
+    !has_flag_in_attrs(&f.attrs, "redact")
+        || get_skip_if(f).is_some()
+        || (options.skip_none && unwrap_type(&f.ty, "Option").is_some())
+}
+
+fn is_flattened(f: &syn::Field) -> bool {
+    has_flag_in_attrs(&f.attrs, "flatten")
+}
+
+// PhantomData and PhantomPinned fields, and those of marker types hidden behind
+// an alias that are marked `#[debug(phantom)]`.
+fn is_marker(f: &syn::Field) -> bool {
+    has_flag_in_attrs(&f.attrs, "phantom") || is_marker_type(&f.ty)
+}
+
+// Skipped, redacted, flattened, marker and `len_only` fields, and those with a
+// `with` formatter or a numeric preset, are never formatted with their own
+// Debug impl, so they don't need a Debug bound. `#[debug(no_bound)]` opts out of it explicitly.
+fn needs_debug_bound(f: &syn::Field) -> bool {
+    !is_skipped(f)
+        && !is_redacted(f)
+        && !is_flattened(f)
+        && !is_marker(f)
+        && !has_flag_in_attrs(&f.attrs, "len_only")
+        && !has_flag_in_attrs(&f.attrs, "no_bound")
+        && get_with_fn(f).is_none()
+        && !NUMERIC_PRESETS
+            .iter()
+            .any(|preset| has_flag_in_attrs(&f.attrs, preset))
+        && get_bitflags(f).is_none()
+}
+
+// `value` is an expression evaluating to a reference to the field.
+fn gen_field_value(f: &syn::Field, value: &TokenStream) -> TokenStream {
+    gen_field_wrapper(f, value).unwrap_or_else(|| value.clone())
+}
+
+// How the field attributes change the way `value` is printed, `None` when it
+// is printed with its own Debug impl.
+fn gen_field_wrapper(f: &syn::Field, value: &TokenStream) -> Option<TokenStream> {
+    let wrapper = if is_redacted(f) {
+        if get_lit_from_attrs(&f.attrs, "redact").is_some() {
+            quote! { &::core::format_args!("*** (len {})", (#value).len()) }
+        } else {
+            quote! { &::core::format_args!("***") }
+        }
+    } else if is_marker(f) {
+        let name = marker_name(&f.ty);
+        quote! { &::derive_debug::fmt::Marker(#name) }
+    } else if has_flag_in_attrs(&f.attrs, "len_only") {
+        let name = type_name(&f.ty);
+        quote! { &::derive_debug::fmt::LenOnly(#name, (#value).len()) }
+    } else if has_flag_in_attrs(&f.attrs, "ref_id") {
+        quote! { &::derive_debug::fmt::Shared(#value) }
+    } else if let Some(Ok(n)) = get_truncate(f) {
+        quote! { &::derive_debug::fmt::Truncated(#value, #n) }
+    } else if let Some(Ok(with_fn)) = get_with_fn(f) {
+        quote! { &::derive_debug::fmt::DebugWith(#value, #with_fn) }
+    } else if let Some(radix) = get_radix(f) {
+        quote! { &::derive_debug::fmt::InRadix(#value, ::derive_debug::fmt::Radix::#radix) }
+    } else if has_flag_in_attrs(&f.attrs, "hex_bytes") {
+        quote! { &::derive_debug::fmt::DebugWith(#value, ::derive_debug::fmt::hex_bytes) }
+    } else if let Some(Ok(flags)) = get_bitflags(f) {
+        quote! { &<#flags>::from_bits_truncate(*#value) }
+    } else if let Some(custom_fmt) = get_custom_fmt(f) {
+        quote! { &::core::format_args!(#custom_fmt, #value) }
+    } else {
+        return None;
+    };
+    Some(wrapper)
+}
+
+const RENAME_RULES: &[&str] = &[
+    "lowercase",
+    "UPPERCASE",
+    "PascalCase",
+    "camelCase",
+    "snake_case",
+    "SCREAMING_SNAKE_CASE",
+    "kebab-case",
+    "SCREAMING-KEBAB-CASE",
+];
+
+fn get_rename_all(attrs: &[syn::Attribute]) -> Option<String> {
+    match get_lit_from_attrs(attrs, "rename_all")? {
+        syn::Lit::Str(s) => Some(s.value()),
+        _ => None,
+    }
+}
+
+fn check_rename_all(attrs: &[syn::Attribute]) -> Option<syn::Error> {
+    let lit = get_lit_from_attrs(attrs, "rename_all")?;
+    match lit {
+        syn::Lit::Str(ref s) if RENAME_RULES.contains(&s.value().as_str()) => None,
+        _ => Some(syn::Error::new_spanned(
+            lit,
+            format!("expected one of {}", RENAME_RULES.join(", ")),
+        )),
+    }
+}
+
+// Splits `snake_case` and `PascalCase` identifiers into lowercase words and
+// joins them back according to one of the `RENAME_RULES`.
+fn apply_rename_rule(name: &str, rule: &str) -> String {
+    let mut words: Vec<String> = Vec::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c == '_' {
+            words.push(String::new());
+        } else {
+            if words.is_empty() || (c.is_uppercase() && prev_lower) {
+                words.push(String::new());
+            }
+            words.last_mut().unwrap().extend(c.to_lowercase());
+        }
+        prev_lower = c.is_lowercase() || c.is_numeric();
+    }
+    words.retain(|w| !w.is_empty());
+
+    let capitalize = |w: &String| {
+        let mut chars = w.chars();
+        chars
+            .next()
+            .map_or_else(String::new, |c| c.to_uppercase().chain(chars).collect())
+    };
+    match rule {
+        "lowercase" => words.concat(),
+        "UPPERCASE" => words.concat().to_uppercase(),
+        "PascalCase" => words.iter().map(capitalize).collect(),
+        "camelCase" => {
+            let pascal: String = words.iter().map(capitalize).collect();
+            let mut chars = pascal.chars();
+            chars
+                .next()
+                .map_or_else(String::new, |c| c.to_lowercase().chain(chars).collect())
+        }
+        "snake_case" => words.join("_"),
+        "SCREAMING_SNAKE_CASE" => words.join("_").to_uppercase(),
+        "kebab-case" => words.join("-"),
+        "SCREAMING-KEBAB-CASE" => words.join("-").to_uppercase(),
+        _ => name.to_owned(),
+    }
+}
+
+// The name printed for a struct, variant or field, `#[debug(rename = "...")]`
+// wins over the `rename_all` rule of the enclosing item.
+fn display_name(ident: &syn::Ident, attrs: &[syn::Attribute], rename_all: Option<&str>) -> String {
+    if let Some(syn::Lit::Str(rename)) = get_lit_from_attrs(attrs, "rename") {
+        return rename.value();
+    }
+    let name = ident.unraw().to_string();
+    match rename_all {
+        Some(rule) => apply_rename_rule(&name, rule),
+        None => name,
+    }
+}
+
+// Output ends with `..` when some fields are skipped.
+fn gen_finish<'a>(mut fields: impl Iterator<Item = &'a syn::Field>) -> syn::Ident {
+    if fields.any(is_skipped) {
+        quote::format_ident!("finish_non_exhaustive")
+    } else {
+        quote::format_ident!("finish")
+    }
+}
+
+// Struct, enum or variant level settings for how the fields are formatted.
+struct FieldsOptions {
+    rename_all: Option<String>,
+    // `#[debug(skip_none)]`, leaves out Option fields that are None.
+    skip_none: bool,
+    // Replaces the fields with a single formatted line.
+    format: Option<syn::Result<DebugFormat>>,
+}
+
+impl FieldsOptions {
+    fn from_attrs(attrs: &[syn::Attribute]) -> Self {
+        FieldsOptions {
+            rename_all: get_rename_all(attrs),
+            skip_none: has_flag_in_attrs(attrs, "skip_none"),
+            format: get_debug_format(attrs),
+        }
+    }
+}
+
+// `#[debug(skip_if = "...")]` names a predicate called with a reference to the
+// field, like `Option::is_none` or `Vec::is_empty`.
+fn get_skip_if(f: &syn::Field) -> Option<syn::Result<syn::Expr>> {
+    match get_lit_from_attrs(&f.attrs, "skip_if")? {
+        syn::Lit::Str(lit) => Some(lit.parse()),
+        lit => Some(Err(syn::Error::new_spanned(
+            lit,
+            "expected `debug(skip_if = \"path::to::fn\")`",
+        ))),
+    }
+}
+
+// Wraps the statement adding a field to the builder in the field's skip
+// condition, if any.
+fn gen_skip_if(
+    f: &syn::Field,
+    value: &TokenStream,
+    options: &FieldsOptions,
+    stmt: TokenStream,
+) -> TokenStream {
+    if let Some(Ok(predicate)) = get_skip_if(f) {
+        quote! {
+            if !(#predicate)(#value) {
+                #stmt
+            }
+        }
+    } else if options.skip_none && unwrap_type(&f.ty, "Option").is_some() {
+        quote! {
+            if ::core::option::Option::is_some(#value) {
+                #stmt
+            }
+        }
+    } else {
+        stmt
+    }
+}
+
+// `#[debug("...")]`, or `#[debug(fmt = "...", args(name = "expr", ...))]`, on
+// a struct or enum variant. The format string is the one of CustomDisplay, its
+// placeholders name fields or the extra args.
+struct DebugFormat {
+    lit: syn::LitStr,
+    args: Vec<(syn::Ident, syn::Expr)>,
+}
+
+fn get_debug_format(attrs: &[syn::Attribute]) -> Option<syn::Result<DebugFormat>> {
+    let mut lit = None;
+    let mut args = Vec::new();
+    for attr in attrs {
+        if !attr.path.is_ident("debug") {
+            continue;
+        }
+        let nested = match attr.parse_meta() {
+            Ok(syn::Meta::List(syn::MetaList { nested, .. })) => nested,
+            Ok(_) => continue,
+            Err(err) => return Some(Err(err)),
+        };
+        for nested in nested {
+            match nested {
+                syn::NestedMeta::Lit(syn::Lit::Str(s)) => lit = Some(s),
+                syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path.is_ident("fmt") => {
+                    match nv.lit {
+                        syn::Lit::Str(s) => lit = Some(s),
+                        other => {
+                            return Some(Err(syn::Error::new_spanned(
+                                other,
+                                "expected `debug(fmt = \"...\")`",
+                            )))
+                        }
+                    }
+                }
+                syn::NestedMeta::Meta(syn::Meta::List(list)) if list.path.is_ident("args") => {
+                    for arg in list.nested {
+                        match parse_format_arg(&arg) {
+                            Ok(arg) => args.push(arg),
+                            Err(err) => return Some(Err(err)),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    match lit {
+        Some(lit) => Some(Ok(DebugFormat { lit, args })),
+        None if !args.is_empty() => Some(Err(syn::Error::new_spanned(
+            &args[0].0,
+            "`debug(args(...))` requires a format string",
+        ))),
+        None => None,
+    }
+}
+
+fn parse_format_arg(arg: &syn::NestedMeta) -> syn::Result<(syn::Ident, syn::Expr)> {
+    if let syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+        ref path,
+        lit: syn::Lit::Str(ref expr),
+        ..
+    })) = arg
+    {
+        if let Some(ident) = path.get_ident() {
+            return Ok((ident.clone(), expr.parse()?));
+        }
+    }
+    Err(syn::Error::new_spanned(arg, "expected `name = \"expr\"`"))
+}
+
+// Writes the fields with a `DebugFormat`. The args are evaluated first, with
+// the named fields in scope as references, and passed after the field values.
+// Skipped fields are rejected by `check`.
+fn gen_debug_format(
+    format: &DebugFormat,
+    fields: &syn::Fields,
+    values: &[TokenStream],
+) -> TokenStream {
+    let names: Vec<_> = format.args.iter().map(|(name, _)| name).collect();
+    let parsed = match display::parse_format(&format.lit, fields, &names) {
+        Ok(parsed) => parsed,
+        Err(err) => return err.to_compile_error(),
+    };
+
+    let arg_values: Vec<_> = (0..format.args.len())
+        .map(|i| {
+            let binding = quote::format_ident!("__arg_{}", i);
+            quote! { #binding }
+        })
+        .collect();
+    // Placeholders of fields with attributes like `redact` or `hex` print what
+    // the field would print in `Name { .. }`, formatted with any trait.
+    let mut all_values: Vec<_> = fields
+        .iter()
+        .zip(values)
+        .map(|(f, value)| match gen_field_wrapper(f, value) {
+            Some(wrapper) => quote! { &::core::format_args!("{:?}", #wrapper) },
+            None => value.clone(),
+        })
+        .collect();
+    all_values.extend(arg_values.iter().cloned());
+    let write = display::gen_write(&parsed, &all_values);
+    if format.args.is_empty() {
+        return write;
+    }
+
+    let (idents, field_values): (Vec<_>, Vec<_>) = fields
+        .iter()
+        .zip(values)
+        .filter_map(|(f, value)| Some((f.ident.as_ref()?, value)))
+        .unzip();
+    let exprs = format.args.iter().map(|(_, expr)| expr);
+    quote! {{
+        let (#(#arg_values,)*) = {
+            #[allow(unused_variables)]
+            let (#(#idents,)*) = (#(#field_values,)*);
+            (#(#exprs,)*)
+        };
+        #write
+    }}
+}
+
+// A format string prints its placeholders unconditionally, so it can't use
+// skipped fields.
+fn check_format_fields(
+    lit: &syn::LitStr,
+    fields: &syn::Fields,
+    parsed: &display::FormatString,
+    errors: &mut Vec<syn::Error>,
+) {
+    let mut used: Vec<_> = parsed.used.iter().map(|(i, _)| *i).collect();
+    used.extend(parsed.counts.iter().copied());
+    used.sort_unstable();
+    used.dedup();
+    for (i, f) in fields.iter().enumerate() {
+        if used.contains(&i) && (is_skipped(f) || get_skip_if(f).is_some()) {
+            let name = match f.ident {
+                Some(ref ident) => ident.unraw().to_string(),
+                None => i.to_string(),
+            };
+            errors.push(syn::Error::new(
+                lit.span(),
+                format!(
+                    "field `{}` is skipped, it can't be used in a format string",
+                    name
+                ),
+            ));
+        }
+    }
+}
+
+// Adds the fields of a struct or variant to `debug_fields`, or, when it has a
+// `DebugFormat`, the fields its placeholders use to `formatted` along with the
+// trait they are formatted with. Fields printed through their attributes, see
+// `gen_debug_format`, are added to `debug_fields` instead.
+fn collect_bound_fields<'a>(
+    fields: &'a syn::Fields,
+    format: Option<syn::Result<DebugFormat>>,
+    debug_fields: &mut Vec<&'a syn::Field>,
+    formatted: &mut Vec<(&'a syn::Field, TokenStream)>,
+) {
+    let format = match format {
+        Some(Ok(format)) => format,
+        _ => {
+            debug_fields.extend(fields.iter());
+            return;
+        }
+    };
+    let names: Vec<_> = format.args.iter().map(|(name, _)| name).collect();
+    if let Ok(parsed) = display::parse_format(&format.lit, fields, &names) {
+        let fields: Vec<_> = fields.iter().collect();
+        for (i, trait_path) in parsed.used {
+            match fields.get(i) {
+                Some(f) if gen_field_wrapper(f, &TokenStream::new()).is_some() => {
+                    debug_fields.push(f)
+                }
+                Some(f) => formatted.push((f, trait_path)),
+                None => {}
+            }
+        }
+    }
+}
+
+// One statement per named field, adding it to the `DebugStruct` in
+// `__builder: &mut DebugStruct`. Flattened fields add their own fields instead.
+fn gen_named_fields<'a>(
+    named: &'a syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+    values: &'a [TokenStream],
+    options: &'a FieldsOptions,
+) -> impl Iterator<Item = TokenStream> + 'a {
+    named
+        .iter()
+        .zip(values)
+        .filter(|(f, _)| !is_skipped(f))
+        .map(move |(f, value)| {
+            let stmt = if is_flattened(f) {
+                quote! { ::derive_debug::fmt::DebugFlatten::fmt_fields(#value, __builder); }
+            } else {
+                let field_name = display_name(
+                    f.ident.as_ref().unwrap(),
+                    &f.attrs,
+                    options.rename_all.as_deref(),
+                );
+                let formatted = gen_field_value(f, value);
+                quote! { __builder.field(#field_name, #formatted); }
+            };
+            gen_skip_if(f, value, options, stmt)
+        })
+}
+
+// Formats a struct or enum variant named `name` from its `fields`, `values`
+// holds one expression per field, see `gen_field_value`.
+fn gen_fmt_fields(
+    name: &str,
+    fields: &syn::Fields,
+    values: &[TokenStream],
+    options: &FieldsOptions,
+) -> TokenStream {
+    if let Some(ref format) = options.format {
+        return match format {
+            Ok(format) => gen_debug_format(format, fields, values),
+            Err(err) => err.to_compile_error(),
+        };
+    }
+
+    match fields {
+        syn::Fields::Named(syn::FieldsNamed { ref named, .. }) => {
+            let fmt_fields = gen_named_fields(named, values, options);
+            let finish = gen_finish(named.iter());
+            quote! {{
+                let __builder = &mut __formatter.debug_struct(#name);
+                #(#fmt_fields)*
+                __builder.#finish()
+            }}
+        }
+        syn::Fields::Unnamed(syn::FieldsUnnamed { ref unnamed, .. }) => {
+            let fmt_fields = unnamed
+                .iter()
+                .zip(values)
+                .filter(|(f, _)| !is_skipped(f))
+                .map(|(f, value)| {
+                    let formatted = gen_field_value(f, value);
+                    let stmt = quote! { __builder.field(#formatted); };
+                    gen_skip_if(f, value, options, stmt)
+                });
+            let finish = gen_finish(unnamed.iter());
+            quote! {{
+                let mut __builder = __formatter.debug_tuple(#name);
+                #(#fmt_fields)*
+                __builder.#finish()
+            }}
+        }
+        syn::Fields::Unit => quote! {
+            __formatter.write_str(#name)
+        },
+    }
+}
+
+// Passes each of the `fields` that would be printed to `__visitor`, see
+// `gen_fmt_fields`. Tuple fields are named by their index, `_name` of the
+// struct or variant isn't used.
+fn gen_visit_fields(
+    _name: &str,
+    fields: &syn::Fields,
+    values: &[TokenStream],
+    options: &FieldsOptions,
+) -> TokenStream {
+    let visit_fields = fields
+        .iter()
+        .zip(values)
+        .enumerate()
+        .filter(|(_, (f, _))| !is_skipped(f))
+        .map(|(i, (f, value))| {
+            let stmt = if is_flattened(f) {
+                quote! { ::derive_debug::fmt::DebugFields::visit_fields(#value, __visitor); }
+            } else {
+                let field_name = match f.ident {
+                    Some(ref ident) => display_name(ident, &f.attrs, options.rename_all.as_deref()),
+                    None => i.to_string(),
+                };
+                let formatted = gen_field_value(f, value);
+                quote! { __visitor.visit(#field_name, #formatted); }
+            };
+            gen_skip_if(f, value, options, stmt)
+        });
+    quote! {{
+        #(#visit_fields)*
+    }}
+}
+
+// Each variant is matched with its fields bound to `__self_0`, `__self_1`, ...
+// so that field names can't shadow the formatter, and the arm's body generated by
+// `gen_body`, e.g. `gen_fmt_fields`. The enum's `rename_all` rule applies to
+// the variant names, its other options to the fields of every variant.
+fn gen_fmt_enum(
+    data: &syn::DataEnum,
+    options: &FieldsOptions,
+    gen_body: fn(&str, &syn::Fields, &[TokenStream], &FieldsOptions) -> TokenStream,
+) -> TokenStream {
+    let arms = data.variants.iter().map(|v| {
+        let ident = &v.ident;
+        let values: Vec<_> = (0..v.fields.len())
+            .map(|i| {
+                let binding = quote::format_ident!("__self_{}", i);
+                quote! { #binding }
+            })
+            .collect();
+        let name = display_name(ident, &v.attrs, options.rename_all.as_deref());
+        let variant_options = FieldsOptions {
+            rename_all: get_rename_all(&v.attrs),
+            skip_none: options.skip_none || has_flag_in_attrs(&v.attrs, "skip_none"),
+            format: get_debug_format(&v.attrs),
+        };
+        let body = gen_body(&name, &v.fields, &values, &variant_options);
+        let pattern = gen_variant_pattern(v, &values, &variant_options);
+        quote! { #pattern => #body, }
+    });
+
+    if data.variants.is_empty() {
+        return quote! { match *self {} };
+    }
+
+    quote! {
+        match self {
+            #(#arms)*
+        }
+    }
+}
+
+// Matches the variant `v` with its fields bound to `bindings`. Unless the
+// variant has a format string, fields whose value is never looked at are
+// matched with `_` so the generated code doesn't trigger unused variable
+// warnings.
+fn gen_variant_pattern(
+    v: &syn::Variant,
+    bindings: &[TokenStream],
+    options: &FieldsOptions,
+) -> TokenStream {
+    let ident = &v.ident;
+    let bindings: Vec<_> = v
+        .fields
+        .iter()
+        .zip(bindings)
+        .map(|(f, binding)| {
+            if reads_value(f, options) || options.format.is_some() {
+                binding.clone()
+            } else {
+                quote! { _ }
+            }
+        })
+        .collect();
+    match v.fields {
+        syn::Fields::Named(syn::FieldsNamed { ref named, .. }) => {
+            let names = named.iter().map(|f| &f.ident);
+            quote! { Self::#ident { #(#names: #bindings),* } }
+        }
+        syn::Fields::Unnamed(_) => quote! { Self::#ident(#(#bindings),*) },
+        syn::Fields::Unit => quote! { Self::#ident },
+    }
+}
+
+// Flags such as `#[debug(transparent)]` on the container or `#[debug(skip)]`
+// on a field.
+fn has_flag_in_attrs(attrs: &[syn::Attribute], flag: &str) -> bool {
+    nested_metas(attrs, "debug").iter().any(|nested| {
+        matches!(nested, syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident(flag))
+    })
+}
+
+// Validates all the `#[debug(...)]` attributes of `input` up front, so that
+// code generation can ignore malformed ones. Every problem found is reported,
+// combined into one error.
+fn check(input: &syn::DeriveInput) -> Option<syn::Error> {
+    let mut errors = Vec::new();
+    let keys = match input.data {
+        syn::Data::Struct(_) => STRUCT_KEYS,
+        syn::Data::Enum(_) => ENUM_KEYS,
+        syn::Data::Union(_) => UNION_KEYS,
+    };
+    check_debug_attrs(&input.attrs, keys, &mut errors);
+    errors.extend(check_rename_all(&input.attrs));
+    if let Some(Err(err)) = get_max_depth(&input.attrs) {
+        errors.push(err);
+    }
+    if let Some(Err(err)) = get_bound(&input.attrs) {
+        errors.push(err);
+    }
+
+    // The struct or variants, each with their own format string.
+    let mut items: Vec<(&syn::Fields, &[syn::Attribute])> = Vec::new();
+    match input.data {
+        syn::Data::Struct(syn::DataStruct { ref fields, .. }) => {
+            if has_flag_in_attrs(&input.attrs, "transparent") && fields.len() != 1 {
+                errors.push(syn::Error::new_spanned(
+                    &input.ident,
+                    "`debug(transparent)` requires a struct with exactly one field",
+                ));
+            }
+            items.push((fields, &input.attrs));
+        }
+        syn::Data::Enum(ref data) => {
+            if get_debug_format(&input.attrs).is_some() {
+                errors.push(syn::Error::new_spanned(
+                    &input.ident,
+                    "format strings go on the variants of an enum",
+                ));
+            }
+            for v in data.variants.iter() {
+                check_debug_attrs(&v.attrs, VARIANT_KEYS, &mut errors);
+                errors.extend(check_rename_all(&v.attrs));
+                items.push((&v.fields, &v.attrs));
+            }
+        }
+        syn::Data::Union(ref data) => {
+            if let Some(Err(err)) = get_union_as(&input.attrs, data) {
+                errors.push(err);
+            }
+            for f in data.fields.named.iter() {
+                check_field_attrs(f, &mut errors);
+            }
+        }
+    }
+
+    for (fields, attrs) in items {
+        match get_debug_format(attrs) {
+            Some(Ok(format)) => {
+                let names: Vec<_> = format.args.iter().map(|(name, _)| name).collect();
+                match display::parse_format(&format.lit, fields, &names) {
+                    Ok(parsed) => check_format_fields(&format.lit, fields, &parsed, &mut errors),
+                    Err(err) => errors.push(err),
+                }
+            }
+            Some(Err(err)) => errors.push(err),
+            None => {}
+        }
+        for f in fields.iter() {
+            check_field_attrs(f, &mut errors);
+            if f.ident.is_none() && is_flattened(f) {
+                errors.push(syn::Error::new_spanned(
+                    f,
+                    "`debug(flatten)` is only supported on named fields",
+                ));
+            }
+        }
+    }
+
+    errors.into_iter().reduce(|mut combined, err| {
+        combined.combine(err);
+        combined
+    })
+}
+
+#[proc_macro_derive(CustomDebug, attributes(debug))]
+pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+
+    let ident = &input.ident;
+    let data = &input.data;
+
+    if let Some(err) = check(&input) {
+        return err.into_compile_error().into();
+    }
+    let options = FieldsOptions::from_attrs(&input.attrs);
+    let packed = is_packed(&input.attrs);
+    let union_field = match data {
+        syn::Data::Union(data) => get_union_as(&input.attrs, data).and_then(Result::ok),
+        _ => None,
+    };
+
+    let (fields, body): (Vec<&syn::Field>, TokenStream) = match data {
+        syn::Data::Struct(syn::DataStruct { fields, .. }) => {
+            let values = gen_self_values(fields, packed);
+            let body = if has_flag_in_attrs(&input.attrs, "transparent") {
+                let value = gen_field_value(fields.iter().next().unwrap(), &values[0]);
+                quote! { ::core::fmt::Debug::fmt(#value, __formatter) }
+            } else {
+                let name = display_name(ident, &input.attrs, None);
+                gen_fmt_fields(&name, fields, &values, &options)
+            };
+            (fields.iter().collect(), body)
+        }
+        syn::Data::Enum(data) => (
+            data.variants.iter().flat_map(|v| v.fields.iter()).collect(),
+            gen_fmt_enum(data, &options, gen_fmt_fields),
+        ),
+        syn::Data::Union(_) => {
+            let name = display_name(ident, &input.attrs, None);
+            match union_field {
+                Some(f) => {
+                    let (field_name, formatted) = gen_union_field(f, packed, &options);
+                    let body = quote! {
+                        __formatter.debug_struct(#name).field(#field_name, #formatted).finish()
+                    };
+                    (vec![f], body)
+                }
+                None => (
+                    Vec::new(),
+                    quote! { __formatter.debug_struct(#name).finish_non_exhaustive() },
+                ),
+            }
+        }
+    };
+    // Every impl checks this before copying out the fields.
+    let packed_asserts = if packed {
+        gen_packed_asserts(fields.iter().copied(), &options)
+    } else {
+        TokenStream::new()
+    };
+
+    // Shared targets are remembered until the outermost impl with `ref_id`
+    // fields is done, nested values are cut off after `max_depth` levels.
+    let has_ref_ids = fields.iter().any(|f| has_flag_in_attrs(&f.attrs, "ref_id"));
+    let mut body = quote! {{
+        #packed_asserts
+        #body
+    }};
+    if has_ref_ids {
+        body = quote! { ::derive_debug::fmt::ref_scope(|| #body) };
+    }
+    if let Some(Ok(max_depth)) = get_max_depth(&input.attrs) {
+        body = quote! { ::derive_debug::fmt::depth_limited::<Self>(#max_depth, __formatter, |__formatter| #body) };
+    }
+
+    let generics = gen_debug_bounds(
+        &input,
+        union_field,
+        &quote! { ::derive_debug::fmt::DebugFlatten },
+    );
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    let mut output = quote! {
+        impl #impl_generics ::core::fmt::Debug for #ident #type_generics #where_clause {
+            fn fmt(&self, __formatter: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                #body
+            }
+        }
+    };
+
+    // Structs with named fields can be flattened into other structs, unless
+    // they have a format string, whose fields may not even be Debug.
+    if let (
+        syn::Data::Struct(syn::DataStruct {
+            fields: struct_fields @ syn::Fields::Named(syn::FieldsNamed { ref named, .. }),
+            ..
+        }),
+        None,
+    ) = (data, &options.format)
+    {
+        let values = gen_self_values(struct_fields, packed);
+        let fmt_fields = gen_named_fields(named, &values, &options);
+        let mut fmt_fields = quote! { #(#fmt_fields)* };
+        if has_ref_ids {
+            fmt_fields = quote! { ::derive_debug::fmt::ref_scope(|| { #fmt_fields }) };
+        }
+        let generics = gen_bounds(
+            &input,
+            &fields,
+            &[],
+            &quote! { ::derive_debug::fmt::DebugFlatten },
+        );
+        let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+        output.extend(quote! {
+            impl #impl_generics ::derive_debug::fmt::DebugFlatten for #ident #type_generics #where_clause {
+                fn fmt_fields(&self, __builder: &mut ::core::fmt::DebugStruct<'_, '_>) {
+                    #packed_asserts
+                    #fmt_fields
+                }
+            }
+        });
+    }
+
+    if has_flag_in_attrs(&input.attrs, "fields") {
+        let mut body = match data {
+            syn::Data::Struct(syn::DataStruct { fields, .. }) => {
+                gen_visit_fields("", fields, &gen_self_values(fields, packed), &options)
+            }
+            syn::Data::Enum(data) => gen_fmt_enum(data, &options, gen_visit_fields),
+            syn::Data::Union(_) => match union_field {
+                Some(f) => {
+                    let (field_name, formatted) = gen_union_field(f, packed, &options);
+                    quote! {{
+                        __visitor.visit(#field_name, #formatted);
+                    }}
+                }
+                None => quote! {{}},
+            },
+        };
+        if has_ref_ids {
+            body = quote! { ::derive_debug::fmt::ref_scope(|| #body) };
+        }
+        let generics = gen_bounds(
+            &input,
+            &fields,
+            &[],
+            &quote! { ::derive_debug::fmt::DebugFields },
+        );
+        let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+        output.extend(quote! {
+            impl #impl_generics ::derive_debug::fmt::DebugFields for #ident #type_generics #where_clause {
+                fn visit_fields(&self, __visitor: &mut dyn ::derive_debug::fmt::FieldVisitor) {
+                    #packed_asserts
+                    #body
+                }
+            }
+        });
+    }
+
+    // println!("{}\n", output.to_string());
+    output.into()
+}
+
+// `&self.field` for each of the struct's `fields`.
+// The fields of `#[repr(packed)]` structs are copied into a temporary instead,
+// as they may be unaligned.
+fn gen_self_values(fields: &syn::Fields, packed: bool) -> Vec<TokenStream> {
+    gen_member_values(&quote! { self }, fields, packed)
+}
+
+// `gen_self_values` of another value than `self`.
+fn gen_member_values(
+    receiver: &TokenStream,
+    fields: &syn::Fields,
+    packed: bool,
+) -> Vec<TokenStream> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| {
+            let member = match f.ident {
+                Some(ref ident) => quote! { #ident },
+                None => syn::Index::from(i).into_token_stream(),
+            };
+            if packed {
+                quote! { &{ #receiver.#member } }
+            } else {
+                quote! { &#receiver.#member }
+            }
+        })
+        .collect()
+}
+
+// `#[repr(packed)]` or `#[repr(C, packed(2))]`.
+fn is_packed(attrs: &[syn::Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("repr"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(syn::Meta::List(syn::MetaList { nested, .. })) => Some(nested),
+            _ => None,
+        })
+        .flatten()
+        .any(|nested| match nested {
+            syn::NestedMeta::Meta(meta) => meta.path().is_ident("packed"),
+            _ => false,
+        })
+}
+
+// Copying a field out of a packed struct only works for Copy types, checking
+// that up front reports the field's type rather than a move out of `self`.
+fn gen_packed_asserts<'a>(
+    fields: impl Iterator<Item = &'a syn::Field>,
+    options: &FieldsOptions,
+) -> TokenStream {
+    let asserts = fields.filter(|f| reads_value(f, options)).map(|f| {
+        let ty = &f.ty;
+        quote_spanned! { ty.span()=>
+            ::derive_debug::fmt::assert_packed_field::<#ty>();
+        }
+    });
+    quote! { #(#asserts)* }
+}
+
+// `#[debug(unsafe_union_as = "field")]` formats a union as if `field` was the
+// one that was last written to, without it unions are printed as
+// `Name { .. }`. The generated Debug impl is safe to call, so the attribute is
+// the user's promise that reading `field` is always sound: either every value
+// of the union is a valid value of the field's type, like for integers, or
+// the field is the one that was written. Otherwise formatting the union, from
+// safe code, is undefined behavior.
+fn get_union_as<'a>(
+    attrs: &[syn::Attribute],
+    data: &'a syn::DataUnion,
+) -> Option<syn::Result<&'a syn::Field>> {
+    let lit = match get_lit_from_attrs(attrs, "unsafe_union_as")? {
+        syn::Lit::Str(lit) => lit,
+        lit => {
+            return Some(Err(syn::Error::new_spanned(
+                lit,
+                "expected `debug(unsafe_union_as = \"field\")`",
+            )))
+        }
+    };
+    let found = data
+        .fields
+        .named
+        .iter()
+        .find(|f| f.ident.as_ref().unwrap().unraw() == lit.value());
+    Some(found.ok_or_else(|| syn::Error::new(lit.span(), format!("no field `{}`", lit.value()))))
+}
+
+// The name and formatted value of a union's `unsafe_union_as` field, see
+// `get_union_as` for why reading it is sound.
+fn gen_union_field(f: &syn::Field, packed: bool, options: &FieldsOptions) -> (String, TokenStream) {
+    let ident = f.ident.as_ref().unwrap();
+    let value = if packed {
+        quote! { &{ unsafe { self.#ident } } }
+    } else {
+        quote! { unsafe { &self.#ident } }
+    };
+    let name = display_name(ident, &f.attrs, options.rename_all.as_deref());
+    (name, gen_field_value(f, &value))
+}
+
+// The generics of the Debug impl of `input`, bounded for every field that is
+// printed. Those of a format string are bounded on the trait they are
+// formatted with, see `gen_bounds`.
+fn gen_debug_bounds(
+    input: &syn::DeriveInput,
+    union_field: Option<&syn::Field>,
+    flatten_trait: &TokenStream,
+) -> syn::Generics {
+    let mut debug_fields = Vec::new();
+    let mut formatted = Vec::new();
+    match input.data {
+        syn::Data::Struct(syn::DataStruct { ref fields, .. }) => {
+            let format = get_debug_format(&input.attrs);
+            collect_bound_fields(fields, format, &mut debug_fields, &mut formatted);
+        }
+        syn::Data::Enum(ref data) => {
+            for v in data.variants.iter() {
+                let format = get_debug_format(&v.attrs);
+                collect_bound_fields(&v.fields, format, &mut debug_fields, &mut formatted);
+            }
+        }
+        syn::Data::Union(_) => debug_fields.extend(union_field),
+    }
+    gen_bounds(input, &debug_fields, &formatted, flatten_trait)
+}
+
+// The generics of `input` with Debug bounds added for `fields`, either inferred
+// or given by `#[debug(bound = "...")]`. Flattened fields are bounded on
+// `flatten_trait` instead, and `formatted` fields on the trait they are
+// formatted with.
+fn gen_bounds(
+    input: &syn::DeriveInput,
+    fields: &[&syn::Field],
+    formatted: &[(&syn::Field, TokenStream)],
+    flatten_trait: &TokenStream,
+) -> syn::Generics {
+    let mut generics = input.generics.clone();
+    if !generics.params.is_empty() {
+        let debug_constrains = if let Some(bound) = get_bound(&input.attrs) {
+            let predicates = bound.into_iter().flatten();
+            quote! { #(#predicates,)* }
+        } else {
+            let mut constrains = gen_type_constrains2(
+                &input.generics,
+                fields
+                    .iter()
+                    .copied()
+                    .filter(|f| needs_debug_bound(f) && get_bound(&f.attrs).is_none()),
+                &quote! { ::core::fmt::Debug },
+            );
+            constrains.extend(gen_flatten_constrains(
+                &input.generics,
+                fields.iter().copied(),
+                flatten_trait,
+            ));
+            constrains.extend(gen_preset_constrains(&input.generics, fields));
+            constrains.extend(display::gen_trait_constrains(&input.generics, formatted));
+            for f in fields {
+                if let Some(Ok(bound)) = get_bound(&f.attrs) {
+                    let predicates = bound.iter();
+                    constrains.extend(quote! { #(#predicates,)* });
+                }
+            }
+            constrains
+        };
+
+        extend_where_clause(&mut generics, debug_constrains);
+    }
+    generics
+}
+
+// The numeric presets print through their own traits instead of Debug, so
+// `#[debug(hex)] x: T` needs `T: RadixDebug` and `#[debug(hex_bytes)] x: T`
+// needs `T: AsRef<[u8]>`.
+fn gen_preset_constrains(generics: &syn::Generics, fields: &[&syn::Field]) -> TokenStream {
+    let bounded = |f: &&syn::Field| {
+        !is_skipped(f)
+            && !is_redacted(f)
+            && !has_flag_in_attrs(&f.attrs, "no_bound")
+            && get_bound(&f.attrs).is_none()
+    };
+    let mut constrains = gen_type_constrains2(
+        generics,
+        fields
+            .iter()
+            .copied()
+            .filter(bounded)
+            .filter(|f| get_radix(f).is_some()),
+        &quote! { ::derive_debug::fmt::RadixDebug },
+    );
+    constrains.extend(gen_type_constrains2(
+        generics,
+        fields
+            .iter()
+            .copied()
+            .filter(bounded)
+            .filter(|f| has_flag_in_attrs(&f.attrs, "hex_bytes")),
+        &quote! { ::core::convert::AsRef<[u8]> },
+    ));
+    constrains
+}
+
+// Adds the comma separated `constrains` to the where clause of `generics`.
+fn extend_where_clause(generics: &mut syn::Generics, constrains: TokenStream) {
+    let predicates =
+        syn::punctuated::Punctuated::<syn::WherePredicate, syn::Token![,]>::parse_terminated
+            .parse2(constrains)
+            .unwrap();
+    generics.make_where_clause().predicates.extend(predicates);
+}
+
+#[proc_macro_derive(DebugDiff, attributes(debug))]
+pub fn derive_debug_diff(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    diff::derive(&input).into()
+}
+
+#[proc_macro_derive(CustomDisplay, attributes(display))]
+pub fn derive_display(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    display::derive(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn unwrap_type<'a>(ty: &'a syn::Type, wrapper: &'_ str) -> Option<&'a syn::Type> {
+    if let syn::Type::Path(syn::TypePath { ref path, .. }) = ty {
+        if path.segments.is_empty() {
+            return None;
+        }
+        let ty = path.segments.last().unwrap();
+        if ty.ident != wrapper {
+            return None;
+        }
+        if let syn::PathArguments::AngleBracketed(syn::AngleBracketedGenericArguments {
+            ref args,
+            ..
+        }) = ty.arguments
+        {
+            if args.len() != 1 {
+                return None;
+            }
+
+            if let syn::GenericArgument::Type(ref ty) = args[0] {
+                return Some(ty);
+            }
+        }
+    };
+    None
+}
+
+// Bounds each type parameter found in the `fields` on `trait_path`.
+fn gen_type_constrains2<'a>(
+    generics: &syn::Generics,
+    fields: impl Iterator<Item = &'a syn::Field>,
+    trait_path: &TokenStream,
+) -> TokenStream {
+    let mut generic_idents = Vec::new();
+    for generic in generics.params.iter() {
+        if let syn::GenericParam::Type(t) = generic {
+            let ident = &t.ident;
+            generic_idents.push(ident);
+        }
+    }
+
+    let mut bounds = Vec::new();
+    for f in fields {
+        search_type(&f.ty, &generic_idents, &mut bounds);
+    }
+
+    let constrains = quote! {
+        #(#bounds: #trait_path,)*
+    };
+
+    constrains
+}
+
+// Flattened fields are bounded on `trait_path` as a whole, as long as their
+// type mentions a type parameter.
+fn gen_flatten_constrains<'a>(
+    generics: &syn::Generics,
+    fields: impl Iterator<Item = &'a syn::Field>,
+    trait_path: &TokenStream,
+) -> TokenStream {
+    let generic_idents: Vec<_> = generics.type_params().map(|t| &t.ident).collect();
+
+    let mut bounds = Vec::new();
+    for f in fields.filter(|f| is_flattened(f) && !is_skipped(f)) {
+        if mentions_generic(f.ty.to_token_stream(), &generic_idents) {
+            add_bound(&f.ty, &mut bounds);
+        }
+    }
+
+    quote! {
+        #(#bounds: #trait_path,)*
+    }
+}
+
+// `PhantomData<..>` and `PhantomPinned`, either imported or by their full path
+// in `std::marker` or `core::marker`. Other types of the same name aren't
+// markers, and aliases can't be seen through.
+fn is_marker_type(ty: &syn::Type) -> bool {
+    let path = match ty {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => path,
+        _ => return false,
+    };
+    let last = match path.segments.last() {
+        Some(last) => last,
+        None => return false,
+    };
+    let is_marker = match last.arguments {
+        syn::PathArguments::None => last.ident == "PhantomPinned",
+        syn::PathArguments::AngleBracketed(_) => last.ident == "PhantomData",
+        syn::PathArguments::Parenthesized(_) => false,
+    };
+
+    let prefix: Vec<_> = path
+        .segments
+        .iter()
+        .rev()
+        .skip(1)
+        .map(|segment| segment.ident.to_string())
+        .collect();
+    let in_marker_module = match prefix.as_slice() {
+        [] => path.leading_colon.is_none(),
+        [module] => module == "marker" && path.leading_colon.is_none(),
+        [module, krate] => module == "marker" && (krate == "std" || krate == "core"),
+        _ => false,
+    };
+    is_marker && in_marker_module
+}
+
+// Markers are printed as written, without their path, e.g. `PhantomData<T>`.
+fn marker_name(ty: &syn::Type) -> String {
+    if let syn::Type::Path(syn::TypePath { qself: None, path }) = ty {
+        if let Some(last) = path.segments.last() {
+            let last: syn::Type = syn::parse_quote!(#last);
+            return type_name(&last);
+        }
+    }
+    type_name(ty)
+}
+
+// Collects the types that need a Debug bound for `ty` to be Debug. Bounds go on
+// the type parameters themselves, on their associated types like `T::Value`,
+// and on qualified paths like `<T as Trait>::Value`. Everything else is walked
+// into, assuming e.g. `Vec<T>` and `(T, &U)` are Debug when their elements are.
+fn search_type(ty: &syn::Type, generics: &[&syn::Ident], bounds: &mut Vec<syn::Type>) {
+    match ty {
+        syn::Type::Path(syn::TypePath { qself: Some(_), .. })
+            if mentions_generic(quote! { #ty }, generics) =>
+        {
+            add_bound(ty, bounds);
+        }
+        syn::Type::Path(syn::TypePath {
+            qself: None,
+            ref path,
+        }) => {
+            // PhantomData<T> is Debug for any T.
+            if is_marker_type(ty) {
+                return;
+            }
+
+            if path.leading_colon.is_none() && generics.contains(&&path.segments[0].ident) {
+                add_bound(ty, bounds);
+                return;
+            }
+
+            for segment in path.segments.iter() {
+                if let syn::PathArguments::AngleBracketed(syn::AngleBracketedGenericArguments {
+                    ref args,
+                    ..
+                }) = segment.arguments
+                {
+                    for arg in args.iter() {
+                        if let syn::GenericArgument::Type(ref ty) = arg {
+                            search_type(ty, generics, bounds);
+                        }
+                    }
+                }
+            }
+        }
+        syn::Type::Reference(syn::TypeReference { ref elem, .. })
+        | syn::Type::Array(syn::TypeArray { ref elem, .. })
+        | syn::Type::Slice(syn::TypeSlice { ref elem, .. })
+        | syn::Type::Paren(syn::TypeParen { ref elem, .. })
+        | syn::Type::Group(syn::TypeGroup { ref elem, .. }) => {
+            search_type(elem, generics, bounds);
+        }
+        syn::Type::Tuple(syn::TypeTuple { ref elems, .. }) => {
+            for elem in elems.iter() {
+                search_type(elem, generics, bounds);
+            }
+        }
+        // Raw pointers and fn pointers are Debug whatever they point to, trait
+        // objects and the rest can't be bounded in a useful way.
+        _ => {}
+    }
+}
+
+fn add_bound(ty: &syn::Type, bounds: &mut Vec<syn::Type>) {
+    let name = quote! { #ty }.to_string();
+    if !bounds.iter().any(|b| quote! { #b }.to_string() == name) {
+        bounds.push(ty.clone());
+    }
+}
+
+fn mentions_generic(tokens: TokenStream, generics: &[&syn::Ident]) -> bool {
+    tokens.into_iter().any(|tt| match tt {
+        proc_macro2::TokenTree::Ident(ref ident) => generics.contains(&ident),
+        proc_macro2::TokenTree::Group(ref group) => mentions_generic(group.stream(), generics),
+        _ => false,
+    })
+}
@@ -0,0 +1,126 @@
+// Support for #[derive(DebugDiff)], which compares two values field by field
+// and lists only the fields whose Debug output differs. Fields of types that
+// derive it too are compared recursively, any other field as a whole.
+//
+// Which of the two the generated code does is picked by method resolution on
+// `(&DiffField(left, right)).diff_into(...)`: `ViaDebugDiff` is implemented
+// for `DiffField` itself, `ViaDebug` only for a reference to it, so the former
+// wins whenever the field's type implements `DebugDiff`.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Debug, Display, Formatter};
+
+/// A field, reached through `path`, whose Debug output differs between the two
+/// compared values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// Field names joined by `.`, tuple fields are named by their index. Empty
+    /// if the values differ as a whole, e.g. are different enum variants.
+    pub path: String,
+    pub left: String,
+    pub right: String,
+}
+
+/// The fields that differ between two values, see `DebugDiff`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Diff {
+    changes: Vec<Change>,
+}
+
+impl Diff {
+    pub fn new() -> Self {
+        Diff::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// Records a change at `path` if `left` and `right` print differently.
+    pub fn compare<T: Debug + ?Sized>(&mut self, path: &str, left: &T, right: &T) {
+        let (left, right) = (debug_string(left), debug_string(right));
+        if left != right {
+            self.changes.push(Change {
+                path: path.into(),
+                left,
+                right,
+            });
+        }
+    }
+
+    /// Adds the changes of a nested value found at `path`.
+    pub fn nest(&mut self, path: &str, inner: Diff) {
+        self.changes
+            .extend(inner.changes.into_iter().map(|change| Change {
+                path: join_path(path, &change.path),
+                ..change
+            }));
+    }
+}
+
+// Shared pointers are numbered from scratch for each side, so that equal
+// values print equally.
+fn debug_string<T: Debug + ?Sized>(value: &T) -> String {
+    #[cfg(feature = "std")]
+    return crate::fmt::ref_scope(|| format!("{:?}", value));
+    #[cfg(not(feature = "std"))]
+    format!("{:?}", value)
+}
+
+fn join_path(outer: &str, inner: &str) -> String {
+    match (outer.is_empty(), inner.is_empty()) {
+        (true, _) => inner.into(),
+        (_, true) => outer.into(),
+        _ => format!("{}.{}", outer, inner),
+    }
+}
+
+/// One change per line: `config.port: 80 != 8080`.
+impl Display for Diff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, change) in self.changes.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            if !change.path.is_empty() {
+                write!(f, "{}: ", change.path)?;
+            }
+            write!(f, "{} != {}", change.left, change.right)?;
+        }
+        Ok(())
+    }
+}
+
+/// Implemented by `#[derive(DebugDiff)]`.
+pub trait DebugDiff: Debug {
+    fn debug_diff(&self, other: &Self) -> Diff;
+}
+
+/// The same field of the two compared values.
+pub struct DiffField<'a, T: ?Sized>(pub &'a T, pub &'a T);
+
+pub trait ViaDebugDiff {
+    fn diff_into(&self, path: &str, diff: &mut Diff);
+}
+
+impl<'a, T: DebugDiff + ?Sized> ViaDebugDiff for DiffField<'a, T> {
+    fn diff_into(&self, path: &str, diff: &mut Diff) {
+        diff.nest(path, self.0.debug_diff(self.1));
+    }
+}
+
+pub trait ViaDebug {
+    fn diff_into(&self, path: &str, diff: &mut Diff);
+}
+
+impl<'a, T: Debug + ?Sized> ViaDebug for &DiffField<'a, T> {
+    fn diff_into(&self, path: &str, diff: &mut Diff) {
+        diff.compare(path, self.0, self.1);
+    }
+}
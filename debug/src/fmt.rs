@@ -0,0 +1,466 @@
+// Formatter functions for `#[debug(with = "...")]`. Each one has the signature
+// `fn(&T, &mut Formatter) -> fmt::Result` and can be named in the attribute by
+// its bare name, e.g. `#[debug(with = "hex_bytes")]`.
+
+// Without the `alloc` feature there are no impls for String, Vec and Box, and
+// `#[debug(max_depth)]` and `#[debug(ref_id)]` need the thread-locals of the
+// `std` feature.
+
+use core::fmt::{self, Debug, Formatter};
+use core::time::Duration;
+
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, string::String, vec::Vec};
+#[cfg(feature = "std")]
+use core::cell::{Cell, RefCell};
+#[cfg(feature = "std")]
+use std::{rc::Rc, sync::Arc};
+
+/// Formats `value` with `f` instead of its own `Debug` impl.
+pub struct DebugWith<'a, T: ?Sized, F>(pub &'a T, pub F);
+
+impl<'a, T, F> Debug for DebugWith<'a, T, F>
+where
+    T: ?Sized,
+    F: Fn(&T, &mut Formatter<'_>) -> fmt::Result,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        (self.1)(self.0, f)
+    }
+}
+
+/// Bytes as one lowercase hex string: `0a1bff`.
+pub fn hex_bytes<T: AsRef<[u8]> + ?Sized>(bytes: &T, f: &mut Formatter<'_>) -> fmt::Result {
+    for b in bytes.as_ref() {
+        write!(f, "{:02x}", b)?;
+    }
+    Ok(())
+}
+
+const SHORT_STR_LEN: usize = 32;
+
+/// Strings longer than 32 characters are cut off with a `…`.
+pub fn short_str<T: AsRef<str> + ?Sized>(s: &T, f: &mut Formatter<'_>) -> fmt::Result {
+    let s = s.as_ref();
+    match s.char_indices().nth(SHORT_STR_LEN) {
+        Some((end, _)) => write!(f, "{:?}…", &s[..end]),
+        None => Debug::fmt(s, f),
+    }
+}
+
+/// Durations of a minute or more as `1h 2m 3s`, shorter ones the way
+/// `Duration`'s `Debug` prints them, e.g. `1.5s` or `250ms`.
+pub fn human_duration(d: &Duration, f: &mut Formatter<'_>) -> fmt::Result {
+    let secs = d.as_secs();
+    if secs < 60 {
+        return Debug::fmt(d, f);
+    }
+
+    let (hours, mins, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        write!(f, "{}h {}m {}s", hours, mins, secs)
+    } else {
+        write!(f, "{}m {}s", mins, secs)
+    }
+}
+
+/// Collections and strings that `#[debug(truncate = N)]` can shorten.
+pub trait Truncate {
+    /// Formats the first `n` elements, followed by `… (k more)` when the
+    /// value is longer than that.
+    fn fmt_truncated(&self, n: usize, f: &mut Formatter<'_>) -> fmt::Result;
+}
+
+impl Truncate for str {
+    fn fmt_truncated(&self, n: usize, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.char_indices().nth(n) {
+            Some((end, _)) => {
+                let more = self[end..].chars().count();
+                write!(f, "{:?}… ({} more)", &self[..end], more)
+            }
+            None => Debug::fmt(self, f),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Truncate for String {
+    fn fmt_truncated(&self, n: usize, f: &mut Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt_truncated(n, f)
+    }
+}
+
+impl<T: Debug> Truncate for [T] {
+    fn fmt_truncated(&self, n: usize, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        list.entries(self.iter().take(n));
+        if self.len() > n {
+            list.entry(&format_args!("… ({} more)", self.len() - n));
+        }
+        list.finish()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Debug> Truncate for Vec<T> {
+    fn fmt_truncated(&self, n: usize, f: &mut Formatter<'_>) -> fmt::Result {
+        self.as_slice().fmt_truncated(n, f)
+    }
+}
+
+impl<T: Debug, const N: usize> Truncate for [T; N] {
+    fn fmt_truncated(&self, n: usize, f: &mut Formatter<'_>) -> fmt::Result {
+        self[..].fmt_truncated(n, f)
+    }
+}
+
+impl<T: Truncate + ?Sized> Truncate for &T {
+    fn fmt_truncated(&self, n: usize, f: &mut Formatter<'_>) -> fmt::Result {
+        (**self).fmt_truncated(n, f)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Truncate + ?Sized> Truncate for Box<T> {
+    fn fmt_truncated(&self, n: usize, f: &mut Formatter<'_>) -> fmt::Result {
+        (**self).fmt_truncated(n, f)
+    }
+}
+
+/// Formats at most the first `.1` elements of `.0`.
+pub struct Truncated<'a, T: ?Sized>(pub &'a T, pub usize);
+
+impl<'a, T: Truncate + ?Sized> Debug for Truncated<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt_truncated(self.1, f)
+    }
+}
+
+/// Only the length of a collection, as `Vec<u8> { len: 4096 }`.
+pub struct LenOnly(pub &'static str, pub usize);
+
+impl Debug for LenOnly {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct(self.0).field("len", &self.1).finish()
+    }
+}
+
+/// Structs whose fields can be inlined into an enclosing struct's output with
+/// `#[debug(flatten)]`. `#[derive(CustomDebug)]` implements it for every
+/// struct with named fields and no format string.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can't be flattened",
+    label = "doesn't implement `DebugFlatten`",
+    note = "only structs with named fields that derive `CustomDebug` without a format string \
+            can be flattened"
+)]
+pub trait DebugFlatten {
+    /// Adds the fields of `self` to `builder`, one by one.
+    fn fmt_fields(&self, builder: &mut fmt::DebugStruct<'_, '_>);
+}
+
+impl<T: DebugFlatten + ?Sized> DebugFlatten for &T {
+    fn fmt_fields(&self, builder: &mut fmt::DebugStruct<'_, '_>) {
+        (**self).fmt_fields(builder)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: DebugFlatten + ?Sized> DebugFlatten for Box<T> {
+    fn fmt_fields(&self, builder: &mut fmt::DebugStruct<'_, '_>) {
+        (**self).fmt_fields(builder)
+    }
+}
+
+/// Receives the fields of a value one at a time, see [`DebugFields`].
+pub trait FieldVisitor {
+    fn visit(&mut self, name: &'static str, value: &dyn Debug);
+}
+
+impl<F: FnMut(&'static str, &dyn Debug)> FieldVisitor for F {
+    fn visit(&mut self, name: &'static str, value: &dyn Debug) {
+        self(name, value)
+    }
+}
+
+/// Structured access to the fields that the `Debug` impl would print, for
+/// loggers that want key=value pairs rather than one string.
+/// `#[derive(CustomDebug)]` implements it for containers marked with
+/// `#[debug(fields)]`, honoring skip, redact and custom formats. Tuple fields
+/// are named by their index, and enums yield the fields of the current variant.
+pub trait DebugFields {
+    fn visit_fields(&self, visitor: &mut dyn FieldVisitor);
+}
+
+impl<T: DebugFields + ?Sized> DebugFields for &T {
+    fn visit_fields(&self, visitor: &mut dyn FieldVisitor) {
+        (**self).visit_fields(visitor)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: DebugFields + ?Sized> DebugFields for Box<T> {
+    fn visit_fields(&self, visitor: &mut dyn FieldVisitor) {
+        (**self).visit_fields(visitor)
+    }
+}
+
+#[cfg(feature = "std")]
+thread_local! {
+    // Number of values of each `depth_limited` type being formatted on this
+    // thread, by type name. Types are removed when no value is left.
+    static DEPTHS: RefCell<Vec<(&'static str, usize)>> = const { RefCell::new(Vec::new()) };
+    // Number of `ref_scope` impls being formatted on this thread, and the
+    // addresses of the Rc/Arc targets printed so far by the outermost one.
+    static REF_SCOPES: Cell<usize> = const { Cell::new(0) };
+    static SEEN: RefCell<Vec<*const ()>> = const { RefCell::new(Vec::new()) };
+}
+
+// Undoes the increment of a thread-local counter, even when formatting panics.
+#[cfg(feature = "std")]
+struct Exit<F: FnMut()>(F);
+
+#[cfg(feature = "std")]
+impl<F: FnMut()> Drop for Exit<F> {
+    fn drop(&mut self) {
+        (self.0)()
+    }
+}
+
+/// Runs `body` of the Debug impl of `T` unless `max_depth` values of `T` are
+/// already being formatted further up, in which case it prints `…` instead.
+/// Each type marked `#[debug(max_depth = N)]` counts its own depth.
+#[cfg(feature = "std")]
+pub fn depth_limited<T: ?Sized>(
+    max_depth: usize,
+    f: &mut Formatter<'_>,
+    body: impl FnOnce(&mut Formatter<'_>) -> fmt::Result,
+) -> fmt::Result {
+    let name = core::any::type_name::<T>();
+    let entered = DEPTHS.with(|depths| {
+        let mut depths = depths.borrow_mut();
+        match depths.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, depth)) if *depth >= max_depth => false,
+            Some((_, depth)) => {
+                *depth += 1;
+                true
+            }
+            None if max_depth == 0 => false,
+            None => {
+                depths.push((name, 1));
+                true
+            }
+        }
+    });
+    if !entered {
+        return f.write_str("…");
+    }
+    let _exit = Exit(|| {
+        DEPTHS.with(|depths| {
+            let mut depths = depths.borrow_mut();
+            if let Some(i) = depths.iter().position(|(n, _)| *n == name) {
+                depths[i].1 -= 1;
+                if depths[i].1 == 0 {
+                    depths.swap_remove(i);
+                }
+            }
+        })
+    });
+    body(f)
+}
+
+/// Runs `body` of an impl with `#[debug(ref_id)]` fields. Shared targets are
+/// remembered until the outermost such impl is done.
+#[cfg(feature = "std")]
+pub fn ref_scope<R>(body: impl FnOnce() -> R) -> R {
+    REF_SCOPES.with(|s| s.set(s.get() + 1));
+    let _exit = Exit(|| {
+        let scopes = REF_SCOPES.with(|s| {
+            s.set(s.get() - 1);
+            s.get()
+        });
+        if scopes == 0 {
+            SEEN.with(|seen| seen.borrow_mut().clear());
+        }
+    });
+    body()
+}
+
+/// Pointers whose target is printed only once per `ref_scope`, see
+/// [`Shared`].
+#[cfg(feature = "std")]
+pub trait SharedDebug {
+    fn fmt_shared(&self, f: &mut Formatter<'_>) -> fmt::Result;
+}
+
+// The first time a target is printed it is labelled `#1=`, later ones print
+// only `#1#`. The target is marked before it is printed so cycles end there.
+#[cfg(feature = "std")]
+fn fmt_once<T: Debug + ?Sized>(ptr: *const T, target: &T, f: &mut Formatter<'_>) -> fmt::Result {
+    let ptr = ptr as *const ();
+    let (id, first) = SEEN.with(|seen| {
+        let mut seen = seen.borrow_mut();
+        match seen.iter().position(|p| *p == ptr) {
+            Some(i) => (i + 1, false),
+            None => {
+                seen.push(ptr);
+                (seen.len(), true)
+            }
+        }
+    });
+    if first {
+        write!(f, "#{}=", id)?;
+        target.fmt(f)
+    } else {
+        write!(f, "#{}#", id)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Debug + ?Sized> SharedDebug for Rc<T> {
+    fn fmt_shared(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fmt_once(Rc::as_ptr(self), self, f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Debug + ?Sized> SharedDebug for Arc<T> {
+    fn fmt_shared(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fmt_once(Arc::as_ptr(self), self, f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<P: SharedDebug> SharedDebug for Option<P> {
+    fn fmt_shared(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Some(ptr) => f.debug_tuple("Some").field(&Shared(ptr)).finish(),
+            None => f.write_str("None"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<P: SharedDebug> SharedDebug for [P] {
+    fn fmt_shared(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter().map(Shared)).finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<P: SharedDebug> SharedDebug for Vec<P> {
+    fn fmt_shared(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.as_slice().fmt_shared(f)
+    }
+}
+
+/// Formats the Rc or Arc `.0`, or an Option or Vec of them, printing each
+/// target only once, see `#[debug(ref_id)]`.
+#[cfg(feature = "std")]
+pub struct Shared<'a, T: ?Sized>(pub &'a T);
+
+#[cfg(feature = "std")]
+impl<'a, T: SharedDebug + ?Sized> Debug for Shared<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt_shared(f)
+    }
+}
+
+/// Fields of `#[repr(packed)]` structs may be unaligned and can't be borrowed,
+/// so `#[derive(CustomDebug)]` copies them out before formatting them.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not `Copy`, so it can't be formatted as a field of a packed struct",
+    label = "not `Copy`",
+    note = "fields of `#[repr(packed)]` structs are copied before they are formatted, \
+            mark the field `#[debug(skip)]` to leave it out"
+)]
+pub trait PackedField: Copy {}
+
+impl<T: Copy> PackedField for T {}
+
+/// Fails to compile, with the message above, unless `T` is `Copy`.
+pub fn assert_packed_field<T: PackedField>() {}
+
+/// Marker fields such as `PhantomData<T>`, printed as the type's name without
+/// requiring `T: Debug`.
+pub struct Marker(pub &'static str);
+
+impl Debug for Marker {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+/// The base used by `#[debug(hex)]`, `#[debug(bin)]` and `#[debug(octal)]`.
+#[derive(Clone, Copy)]
+pub enum Radix {
+    Hex,
+    Bin,
+    Octal,
+}
+
+/// Integers, and collections of them, that can be printed in another base.
+pub trait RadixDebug {
+    fn fmt_radix(&self, radix: Radix, f: &mut Formatter<'_>) -> fmt::Result;
+}
+
+macro_rules! radix_debug {
+    ($($int:ty)*) => {$(
+        impl RadixDebug for $int {
+            fn fmt_radix(&self, radix: Radix, f: &mut Formatter<'_>) -> fmt::Result {
+                match radix {
+                    Radix::Hex => write!(f, "{:#x}", self),
+                    Radix::Bin => write!(f, "{:#b}", self),
+                    Radix::Octal => write!(f, "{:#o}", self),
+                }
+            }
+        }
+    )*};
+}
+
+radix_debug!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
+
+impl<T: RadixDebug> RadixDebug for [T] {
+    fn fmt_radix(&self, radix: Radix, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.iter().map(|value| InRadix(value, radix)))
+            .finish()
+    }
+}
+
+impl<T: RadixDebug, const N: usize> RadixDebug for [T; N] {
+    fn fmt_radix(&self, radix: Radix, f: &mut Formatter<'_>) -> fmt::Result {
+        self[..].fmt_radix(radix, f)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: RadixDebug> RadixDebug for Vec<T> {
+    fn fmt_radix(&self, radix: Radix, f: &mut Formatter<'_>) -> fmt::Result {
+        self.as_slice().fmt_radix(radix, f)
+    }
+}
+
+impl<T: RadixDebug> RadixDebug for Option<T> {
+    fn fmt_radix(&self, radix: Radix, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Some(value) => f.debug_tuple("Some").field(&InRadix(value, radix)).finish(),
+            None => f.write_str("None"),
+        }
+    }
+}
+
+impl<T: RadixDebug + ?Sized> RadixDebug for &T {
+    fn fmt_radix(&self, radix: Radix, f: &mut Formatter<'_>) -> fmt::Result {
+        (**self).fmt_radix(radix, f)
+    }
+}
+
+/// Formats the integer, or every integer in the collection, `.0` in `.1`.
+pub struct InRadix<'a, T: ?Sized>(pub &'a T, pub Radix);
+
+impl<'a, T: RadixDebug + ?Sized> Debug for InRadix<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt_radix(self.1, f)
+    }
+}
@@ -0,0 +1,62 @@
+// Enums are formatted the same way the standard library's derive does it: unit
+// variants by name, tuple variants with `debug_tuple` and struct variants with
+// `debug_struct`.
+//
+// Field attributes such as #[debug = "..."] apply inside variants too, and the
+// bounds are inferred from the fields of all variants.
+//
+//
+// Resources:
+//
+//   - The syntax tree for an enum:
+//     https://docs.rs/syn/1.0/syn/struct.DataEnum.html
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+#[derive(CustomDebug)]
+pub enum Message<T, E> {
+    Quit,
+    Move {
+        x: i32,
+        #[debug = "0b{:08b}"]
+        flags: u8,
+    },
+    Write(String, #[debug = "{:#x}"] u32),
+    Value(T),
+    Error { error: E, fmt: PhantomData<E> },
+}
+
+#[derive(CustomDebug)]
+pub enum Never {}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+
+    let quit: Message<u8, ()> = Message::Quit;
+    assert_eq!(format!("{:?}", quit), "Quit");
+
+    let moved: Message<u8, ()> = Message::Move { x: -1, flags: 0b101 };
+    assert_eq!(format!("{:?}", moved), "Move { x: -1, flags: 0b00000101 }");
+
+    let write: Message<u8, ()> = Message::Write("hi".to_owned(), 255);
+    assert_eq!(format!("{:?}", write), r#"Write("hi", 0xff)"#);
+
+    let value: Message<u8, ()> = Message::Value(7);
+    assert_eq!(format!("{:?}", value), "Value(7)");
+
+    let error: Message<u8, &str> = Message::Error {
+        error: "oops",
+        fmt: PhantomData,
+    };
+    assert_eq!(
+        format!("{:?}", error),
+        r#"Error { error: "oops", fmt: PhantomData<E> }"#
+    );
+
+    assert_debug::<Never>();
+    assert_debug::<Message<u8, PhantomData<NotDebug>>>();
+}
@@ -0,0 +1,46 @@
+// Tuple structs are formatted with `debug_tuple` and unit structs by their
+// name alone, like the standard library's derive does.
+//
+// Newtypes are often clearer when they print as the value they wrap. With
+// #[debug(transparent)] on a struct with a single field, the generated impl
+// forwards straight to that field, so `UserId(42)` prints as `42`.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Point(i32, #[debug = "{:+}"] i32);
+
+#[derive(CustomDebug)]
+pub struct Marker;
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct UserId(u64);
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Name {
+    name: String,
+}
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Mask(#[debug = "0b{:04b}"] u8);
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Wrapper<T>(T);
+
+fn main() {
+    assert_eq!(format!("{:?}", Point(1, 2)), "Point(1, +2)");
+    assert_eq!(format!("{:?}", Marker), "Marker");
+    assert_eq!(format!("{:?}", UserId(42)), "42");
+    assert_eq!(
+        format!("{:?}", Name {
+            name: "ferris".to_owned()
+        }),
+        r#""ferris""#
+    );
+    assert_eq!(format!("{:?}", Mask(5)), "0b0101");
+    assert_eq!(format!("{:#?}", Wrapper(vec![1])), "[\n    1,\n]");
+}
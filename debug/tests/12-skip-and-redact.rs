@@ -0,0 +1,69 @@
+// Some fields must never end up in logs, and some are just noise.
+//
+// A field with #[debug(skip)] is left out of the output entirely, which then
+// ends with `..` to show that not every field was printed.
+//
+// A field with #[debug(redact)] is printed as `***`, or with
+// #[debug(redact = "len")] as `*** (len N)`. Its type doesn't need to
+// implement Debug, so neither skipped nor redacted fields contribute to the
+// inferred bounds.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+#[derive(CustomDebug)]
+pub struct Login<T, S> {
+    user: String,
+    #[debug(redact)]
+    password: T,
+    #[debug(redact = "len")]
+    token: String,
+    #[debug(skip)]
+    session: S,
+}
+
+#[derive(CustomDebug)]
+pub struct Key(u32, #[debug(skip)] Vec<u8>);
+
+#[derive(CustomDebug)]
+pub enum Auth<T> {
+    Basic {
+        user: String,
+        #[debug(redact)]
+        password: T,
+    },
+    Token(#[debug(redact = "len")] String, #[debug(skip)] T),
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+
+    let login = Login {
+        user: "ferris".to_owned(),
+        password: NotDebug,
+        token: "abcdef".to_owned(),
+        session: NotDebug,
+    };
+    assert_eq!(
+        format!("{:?}", login),
+        r#"Login { user: "ferris", password: ***, token: *** (len 6), .. }"#
+    );
+
+    assert_eq!(format!("{:?}", Key(1, vec![2])), "Key(1, ..)");
+
+    let basic = Auth::Basic {
+        user: "ferris".to_owned(),
+        password: NotDebug,
+    };
+    assert_eq!(
+        format!("{:?}", basic),
+        r#"Basic { user: "ferris", password: *** }"#
+    );
+    let token = Auth::Token("abc".to_owned(), NotDebug);
+    assert_eq!(format!("{:?}", token), "Token(*** (len 3), ..)");
+
+    assert_debug::<Login<NotDebug, NotDebug>>();
+    assert_debug::<Auth<NotDebug>>();
+}
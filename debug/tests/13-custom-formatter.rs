@@ -0,0 +1,63 @@
+// A format string only sees the field in isolation through `format_args!`. For
+// anything more involved, #[debug(with = "path::to::fn")] formats the field by
+// calling a function with the signature:
+//
+//     fn(&T, &mut fmt::Formatter) -> fmt::Result
+//
+// A few such functions ship in `derive_debug::fmt` and can be named by their
+// bare name: `hex_bytes`, `short_str` and `human_duration`.
+//
+// The field's own Debug impl is never used, so no `T: Debug` bound is needed.
+
+use derive_debug::CustomDebug;
+use std::fmt::{self, Debug};
+use std::time::Duration;
+
+mod units {
+    use std::fmt;
+
+    pub fn kib(bytes: &usize, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} KiB", bytes / 1024)
+    }
+}
+
+fn opaque<T>(_: &T, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("<opaque>")
+}
+
+#[derive(CustomDebug)]
+pub struct Download<T> {
+    #[debug(with = "units::kib")]
+    size: usize,
+    #[debug(with = "hex_bytes")]
+    sha: Vec<u8>,
+    #[debug(with = "short_str")]
+    url: String,
+    #[debug(with = "human_duration")]
+    elapsed: Duration,
+    #[debug(with = "opaque")]
+    handle: T,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+
+    let download = Download {
+        size: 4096,
+        sha: vec![0xde, 0xad, 0x0b],
+        url: "https://example.com/very/long/path/to/archive.tar.gz".to_owned(),
+        elapsed: Duration::from_secs(3725),
+        handle: NotDebug,
+    };
+
+    let expected = concat!(
+        "Download { size: 4 KiB, sha: dead0b, ",
+        r#"url: "https://example.com/very/long/pa"…, "#,
+        "elapsed: 1h 2m 5s, handle: <opaque> }",
+    );
+    assert_eq!(format!("{:?}", download), expected);
+
+    assert_debug::<Download<NotDebug>>();
+}
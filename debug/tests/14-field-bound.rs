@@ -0,0 +1,65 @@
+// The field level counterpart of the escape hatch from test 08. A
+// #[debug(bound = "...")] attribute on a field replaces only the bounds that
+// would have been inferred from that field's type, the bounds inferred from
+// the other fields are kept.
+//
+//     impl<T: Trait, U> Debug for Wrapper<T, U>
+//     where
+//         U: Debug,
+//         T::Value: Debug,
+//     {...}
+//
+// An empty bound, #[debug(bound = "")], means the field needs no bound at all,
+// for example because its Debug impl holds for any type parameter.
+
+use derive_debug::CustomDebug;
+use std::fmt::{self, Debug};
+
+pub trait Trait {
+    type Value;
+}
+
+#[derive(CustomDebug)]
+pub struct Wrapper<T: Trait, U, V> {
+    #[debug(bound = "T::Value: Debug")]
+    field: Field<T>,
+    normal: U,
+    #[debug(bound = "")]
+    handle: Handle<V>,
+}
+
+#[derive(CustomDebug)]
+pub struct Field<T: Trait> {
+    values: Vec<T::Value>,
+}
+
+pub struct Handle<V>(V);
+
+impl<V> Debug for Handle<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Handle")
+    }
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct Id;
+    struct NotDebug;
+
+    impl Trait for Id {
+        type Value = u8;
+    }
+
+    assert_debug::<Wrapper<Id, u8, NotDebug>>();
+
+    let wrapper: Wrapper<Id, &str, NotDebug> = Wrapper {
+        field: Field { values: vec![1] },
+        normal: "x",
+        handle: Handle(NotDebug),
+    };
+    assert_eq!(
+        format!("{:?}", wrapper),
+        r#"Wrapper { field: Field { values: [1] }, normal: "x", handle: Handle }"#
+    );
+}
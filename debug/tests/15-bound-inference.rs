@@ -0,0 +1,63 @@
+// Type parameters can show up in field types in many more forms than a plain
+// path like `Vec<T>`. The inferred bounds need to see through all of them:
+//
+//   - references, `&'a T` and `&'a mut T`,
+//   - tuples, `(T, U)`,
+//   - arrays and slices, `[T; 4]` and `Box<[T]>`,
+//   - qualified paths, `<T as Trait>::Value` is bounded as a whole.
+//
+// Some forms are Debug no matter what the type parameter is, so they should
+// not add a bound: raw pointers and fn pointers like `fn(T) -> U`.
+//
+// Each bound is added only once even when several fields need it.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+pub trait Trait {
+    type Value;
+}
+
+#[derive(CustomDebug)]
+pub struct Everything<'a, A, B, C, D, E, F: Trait, G, H> {
+    by_ref: &'a A,
+    by_mut: &'a mut A,
+    pair: (B, Option<B>),
+    array: [C; 4],
+    slice: Box<[D]>,
+    nested: Vec<(&'a E, [E; 1])>,
+    projection: Option<<F as Trait>::Value>,
+    callback: fn(G) -> G,
+    pointer: *const H,
+}
+
+fn assert_debug<T: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+    struct Id;
+
+    impl Trait for Id {
+        type Value = u8;
+    }
+
+    assert_debug::<Everything<u8, u8, u8, u8, u8, Id, NotDebug, NotDebug>>();
+
+    let mut x = 1;
+    let value = Everything::<_, _, _, _, _, Id, _, NotDebug> {
+        by_ref: &0,
+        by_mut: &mut x,
+        pair: ('b', None),
+        array: [1u8; 4],
+        slice: vec!["d"].into_boxed_slice(),
+        nested: Vec::<(&u8, [u8; 1])>::new(),
+        projection: Some(7),
+        callback: |g: u8| g,
+        pointer: std::ptr::null(),
+    };
+    let debug = format!("{:?}", value);
+    assert!(debug.starts_with(concat!(
+        "Everything { by_ref: 0, by_mut: 1, pair: ('b', None), array: [1, 1, 1, 1], ",
+        r#"slice: ["d"], nested: [], projection: Some(7), callback: "#,
+    )));
+}
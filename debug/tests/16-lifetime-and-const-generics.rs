@@ -0,0 +1,79 @@
+// Besides type parameters, the struct may have lifetime parameters, const
+// generic parameters, type parameters with defaults and a where clause of its
+// own. All of them need to carry over into the generated impl, with the
+// inferred bounds merged into the existing where clause. Only the type
+// parameters that are actually formatted get a bound.
+//
+// Field and struct names should print the way they are written, a raw
+// identifier like `r#type` prints as `type`.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+#[derive(CustomDebug)]
+pub struct Header<const N: usize> {
+    len: usize,
+}
+
+#[derive(CustomDebug)]
+pub struct Frame<'a, T, const N: usize> {
+    header: Header<N>,
+    data: [u8; N],
+    payload: &'a [T],
+}
+
+#[derive(CustomDebug)]
+pub struct Defaulted<T, U = PhantomData<T>>
+where
+    T: Clone
+{
+    value: T,
+    marker: U,
+}
+
+#[derive(CustomDebug)]
+pub struct Borrowed<'a, 'b: 'a, T: ?Sized> {
+    name: &'a str,
+    r#type: &'b T,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+
+    let payload = [1u16, 2];
+    let frame = Frame {
+        header: Header::<2> { len: 2 },
+        data: [0xa, 0xb],
+        payload: &payload,
+    };
+    assert_eq!(
+        format!("{:?}", frame),
+        "Frame { header: Header { len: 2 }, data: [10, 11], payload: [1, 2] }"
+    );
+
+    let defaulted: Defaulted<u8> = Defaulted {
+        value: 1,
+        marker: PhantomData,
+    };
+    assert_eq!(
+        format!("{:?}", defaulted),
+        "Defaulted { value: 1, marker: PhantomData<u8> }"
+    );
+
+    let borrowed = Borrowed {
+        name: "x",
+        r#type: "str",
+    };
+    assert_eq!(
+        format!("{:?}", borrowed),
+        r#"Borrowed { name: "x", type: "str" }"#
+    );
+
+    assert_debug::<Header<4>>();
+    assert_debug::<Frame<'static, u8, 4>>();
+    assert_debug::<Borrowed<'static, 'static, [u8]>>();
+    assert_debug::<Defaulted<u8, PhantomData<NotDebug>>>();
+}
@@ -0,0 +1,49 @@
+// Debug output of FFI wrappers reads best when it uses the names from the C
+// headers. #[debug(rename = "...")] changes the printed name of a field, a
+// struct or an enum variant.
+//
+// #[debug(rename_all = "...")] applies a case convention to every field name
+// of a struct, or to every variant name of an enum, the same way serde does.
+// A rename on the individual item wins over it. The supported conventions
+// are lowercase, UPPERCASE, PascalCase, camelCase, snake_case,
+// SCREAMING_SNAKE_CASE, kebab-case and SCREAMING-KEBAB-CASE.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(rename = "struct sockaddr_in", rename_all = "camelCase")]
+pub struct SockAddr {
+    sin_family: u16,
+    #[debug(rename = "sin_port")]
+    port: u16,
+    sin_addr_raw: u32,
+}
+
+#[derive(CustomDebug)]
+#[debug(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Status {
+    NotFound,
+    #[debug(rename = "EAGAIN")]
+    TryAgain,
+    #[debug(rename_all = "kebab-case")]
+    Failed { error_code: i32 },
+}
+
+fn main() {
+    let addr = SockAddr {
+        sin_family: 2,
+        port: 80,
+        sin_addr_raw: 1,
+    };
+    assert_eq!(
+        format!("{:?}", addr),
+        "struct sockaddr_in { sinFamily: 2, sin_port: 80, sinAddrRaw: 1 }"
+    );
+
+    assert_eq!(format!("{:?}", Status::NotFound), "NOT_FOUND");
+    assert_eq!(format!("{:?}", Status::TryAgain), "EAGAIN");
+    assert_eq!(
+        format!("{:?}", Status::Failed { error_code: -1 }),
+        "FAILED { error-code: -1 }"
+    );
+}
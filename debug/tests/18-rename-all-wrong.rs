@@ -0,0 +1,11 @@
+// Unknown case conventions are reported at the attribute.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(rename_all = "Title Case")]
+pub struct SockAddr {
+    sin_family: u16,
+}
+
+fn main() {}
@@ -0,0 +1,92 @@
+// The same crate also provides #[derive(CustomDisplay)], for the `impl Display`
+// boilerplate that otherwise gets written by hand.
+//
+// The format string goes in a #[display("...")] attribute on the struct, or on
+// each enum variant. Placeholders refer to fields by name, or by index for
+// tuple fields, and take the usual format specs. Unit variants without an
+// attribute print their name.
+//
+// Bounds are inferred like for CustomDebug, but only from the fields that are
+// used, and on the trait each placeholder formats with: `{1:08x}` needs
+// LowerHex, `{name}` needs Display.
+//
+// A width or precision may come from another field, `{name:>width$}`.
+
+use derive_debug::CustomDisplay;
+
+#[derive(CustomDisplay)]
+#[display("{name} <{email}>")]
+pub struct Contact {
+    name: String,
+    email: String,
+}
+
+#[derive(CustomDisplay)]
+#[display("#{1:08x} {{{0}}}")]
+pub struct Id<T>(T, u8);
+
+#[derive(CustomDisplay)]
+pub enum Event<T, U> {
+    #[display("connected to {host}:{port}")]
+    Connected { host: T, port: u16 },
+    #[display("failed: {0:?} ({0})")]
+    Failed(U),
+    Closed,
+}
+
+#[derive(CustomDisplay)]
+#[display("{value}")]
+pub struct Partial<T, U> {
+    value: T,
+    unused: U,
+}
+
+#[derive(CustomDisplay)]
+#[display("[{name:>width$}] {value:.prec$}")]
+pub struct Aligned {
+    name: String,
+    width: usize,
+    value: f64,
+    prec: usize,
+}
+
+#[derive(CustomDisplay)]
+pub enum Column {
+    #[display("{0:<1$}|")]
+    Cell(String, usize),
+}
+
+fn assert_display<F: std::fmt::Display>() {}
+
+fn main() {
+    struct NotDisplay;
+
+    let contact = Contact {
+        name: "Ferris".to_owned(),
+        email: "ferris@example.com".to_owned(),
+    };
+    assert_eq!(contact.to_string(), "Ferris <ferris@example.com>");
+
+    assert_eq!(Id("x", 255).to_string(), "#000000ff {x}");
+
+    let connected: Event<&str, u8> = Event::Connected {
+        host: "localhost",
+        port: 8080,
+    };
+    assert_eq!(connected.to_string(), "connected to localhost:8080");
+    let failed: Event<&str, &str> = Event::Failed("eof");
+    assert_eq!(failed.to_string(), r#"failed: "eof" (eof)"#);
+    let closed: Event<u8, u8> = Event::Closed;
+    assert_eq!(closed.to_string(), "Closed");
+
+    let aligned = Aligned {
+        name: "pi".to_owned(),
+        width: 4,
+        value: 3.14159,
+        prec: 2,
+    };
+    assert_eq!(aligned.to_string(), "[  pi] 3.14");
+    assert_eq!(Column::Cell("ab".to_owned(), 4).to_string(), "ab  |");
+
+    assert_display::<Partial<u8, NotDisplay>>();
+}
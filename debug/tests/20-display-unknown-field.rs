@@ -0,0 +1,28 @@
+// Placeholders that don't name a field are reported at the format string, and
+// so are widths and precisions. `.*` would take a positional argument that
+// doesn't exist once the placeholders are rewritten.
+
+use derive_debug::CustomDisplay;
+
+#[derive(CustomDisplay)]
+#[display("{name} <{mail}>")]
+pub struct Contact {
+    name: String,
+    email: String,
+}
+
+#[derive(CustomDisplay)]
+#[display("{value:>size$}")]
+pub struct Padded {
+    value: String,
+    width: usize,
+}
+
+#[derive(CustomDisplay)]
+#[display("{value:.*}")]
+pub struct Rounded {
+    value: f64,
+    prec: usize,
+}
+
+fn main() {}
@@ -0,0 +1,102 @@
+// Sparse structs full of `field: None` are hard to read. A field with
+// #[debug(skip_if = "...")] is left out whenever the predicate returns true
+// for it. The predicate is called with a reference to the field, so methods
+// like `Option::is_none` and `Vec::is_empty` can be named directly.
+//
+// #[debug(skip_none)] on the struct or enum does the same for every Option
+// field that is None.
+//
+// Unlike #[debug(skip)], a field left out this way is not signaled with `..`
+// at the end of the output. Redacted fields can be left out too, the condition
+// still sees their real value.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Config {
+    name: String,
+    #[debug(skip_if = "Option::is_none")]
+    port: Option<u16>,
+    #[debug(skip_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[debug(skip_if = "|retries: &u32| *retries == 3")]
+    retries: u32,
+    timeout: Option<u32>,
+}
+
+#[derive(CustomDebug)]
+#[debug(skip_none)]
+pub struct Sparse {
+    a: Option<u8>,
+    b: Option<u8>,
+    c: u8,
+}
+
+#[derive(CustomDebug)]
+#[debug(skip_none)]
+pub enum Request {
+    Get {
+        path: String,
+        query: Option<String>,
+    },
+    Head(Option<String>, #[debug(skip_if = "str::is_empty")] &'static str),
+    Auth {
+        #[debug(redact)]
+        token: Option<String>,
+    },
+}
+
+#[derive(CustomDebug)]
+pub enum Login {
+    Password(#[debug(redact, skip_if = "Option::is_none")] Option<String>),
+}
+
+fn main() {
+    let config = Config {
+        name: "db".to_owned(),
+        port: None,
+        tags: vec![],
+        retries: 3,
+        timeout: None,
+    };
+    assert_eq!(
+        format!("{:?}", config),
+        r#"Config { name: "db", timeout: None }"#
+    );
+
+    let config = Config {
+        name: "db".to_owned(),
+        port: Some(5432),
+        tags: vec!["primary".to_owned()],
+        retries: 5,
+        timeout: Some(30),
+    };
+    assert_eq!(
+        format!("{:?}", config),
+        r#"Config { name: "db", port: Some(5432), tags: ["primary"], retries: 5, timeout: Some(30) }"#
+    );
+
+    let sparse = Sparse {
+        a: None,
+        b: Some(2),
+        c: 3,
+    };
+    assert_eq!(format!("{:?}", sparse), "Sparse { b: Some(2), c: 3 }");
+
+    let get = Request::Get {
+        path: "/".to_owned(),
+        query: None,
+    };
+    assert_eq!(format!("{:?}", get), r#"Get { path: "/" }"#);
+    assert_eq!(format!("{:?}", Request::Head(None, "")), "Head");
+
+    let auth = Request::Auth {
+        token: Some("hunter2".to_owned()),
+    };
+    assert_eq!(format!("{:?}", auth), "Auth { token: *** }");
+    assert_eq!(format!("{:?}", Request::Auth { token: None }), "Auth");
+
+    let login = Login::Password(Some("hunter2".to_owned()));
+    assert_eq!(format!("{:?}", login), "Password(***)");
+    assert_eq!(format!("{:?}", Login::Password(None)), "Password");
+}
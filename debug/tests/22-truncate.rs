@@ -0,0 +1,76 @@
+// Large buffers and long strings drown out the rest of the output. A field
+// with #[debug(truncate = N)] prints only its first N elements (or chars),
+// followed by a note of how many were left out. Vec, arrays, slices, String
+// and &str are supported.
+//
+// #[debug(len_only)] replaces the contents entirely with the length of the
+// collection, labelled with the field's type as written.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Packet<'a> {
+    #[debug(truncate = 3)]
+    header: [u8; 8],
+    #[debug(truncate = 4)]
+    body: Vec<u16>,
+    #[debug(truncate = 5)]
+    note: String,
+    #[debug(truncate = 5)]
+    short: &'a str,
+    #[debug(truncate = 2)]
+    tail: &'a [u8],
+    #[debug(len_only)]
+    payload: Vec<u8>,
+    #[debug(len_only)]
+    magic: &'static [u8],
+}
+
+#[derive(CustomDebug)]
+pub struct Scratch<'a> {
+    #[debug(len_only)]
+    buf: &'a mut [u8],
+}
+
+// No `T: Debug` bound is needed for a field that only prints its length.
+pub struct NotDebug;
+
+#[derive(CustomDebug)]
+pub struct Queue<T> {
+    #[debug(len_only)]
+    items: Vec<T>,
+}
+
+fn main() {
+    let packet = Packet {
+        header: [1, 2, 3, 4, 5, 6, 7, 8],
+        body: (0..10).collect(),
+        note: "hello, world".to_owned(),
+        short: "héllo",
+        tail: &[9, 8],
+        payload: vec![0; 4096],
+        magic: b"\x7fELF",
+    };
+
+    assert_eq!(
+        format!("{:?}", packet),
+        "Packet { header: [1, 2, 3, … (5 more)], body: [0, 1, 2, 3, … (6 more)], \
+         note: \"hello\"… (7 more), short: \"héllo\", tail: [9, 8], \
+         payload: Vec<u8> { len: 4096 }, magic: &'static [u8] { len: 4 } }",
+    );
+
+    let mut buf = [0; 16];
+    let scratch = Scratch { buf: &mut buf };
+    assert_eq!(
+        format!("{:?}", scratch),
+        "Scratch { buf: &'a mut [u8] { len: 16 } }",
+    );
+
+    let queue = Queue {
+        items: vec![NotDebug, NotDebug],
+    };
+    assert_eq!(
+        format!("{:?}", queue),
+        "Queue { items: Vec<T> { len: 2 } }",
+    );
+}
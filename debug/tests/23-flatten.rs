@@ -0,0 +1,77 @@
+// Wrapper types that add a field or two around the value they wrap lead to
+// deeply nested output. A field with #[debug(flatten)] has its own fields
+// inlined into the enclosing struct instead of being printed as a nested
+// `Inner { .. }` block.
+//
+// Every struct with named fields that derives CustomDebug implements the
+// derive_debug::fmt::DebugFlatten trait, which adds the fields one by one to
+// a `DebugStruct`. Generic flattened fields are bounded on that trait rather
+// than on Debug.
+
+use derive_debug::CustomDebug;
+use std::time::Duration;
+
+#[derive(CustomDebug)]
+pub struct Request {
+    method: &'static str,
+    #[debug(rename = "url")]
+    path: String,
+}
+
+#[derive(CustomDebug)]
+pub struct Timed<T> {
+    #[debug(flatten)]
+    inner: T,
+    elapsed: Duration,
+}
+
+#[derive(CustomDebug)]
+pub struct Tagged<T> {
+    tag: &'static str,
+    #[debug(flatten)]
+    inner: Box<T>,
+}
+
+#[derive(CustomDebug)]
+pub enum Event {
+    Request {
+        id: u32,
+        #[debug(flatten)]
+        request: Request,
+    },
+}
+
+fn main() {
+    let request = Timed {
+        inner: Request {
+            method: "GET",
+            path: "/index.html".to_owned(),
+        },
+        elapsed: Duration::from_millis(5),
+    };
+    assert_eq!(
+        format!("{:?}", request),
+        "Timed { method: \"GET\", url: \"/index.html\", elapsed: 5ms }",
+    );
+
+    let tagged = Tagged {
+        tag: "slow",
+        inner: Box::new(request),
+    };
+    assert_eq!(
+        format!("{:?}", tagged),
+        "Tagged { tag: \"slow\", method: \"GET\", url: \"/index.html\", elapsed: 5ms }",
+    );
+
+    let event = Event::Request {
+        id: 7,
+        request: Request {
+            method: "POST",
+            path: "/".to_owned(),
+        },
+    };
+    assert_eq!(
+        format!("{:?}", event),
+        "Request { id: 7, method: \"POST\", url: \"/\" }",
+    );
+}
@@ -0,0 +1,81 @@
+// Structured loggers want key=value pairs rather than a single `{:?}` string.
+// With #[debug(fields)] on the container the derive also implements
+// derive_debug::fmt::DebugFields, which hands each field that the Debug impl
+// would print to a FieldVisitor along with its name.
+//
+// The visitor sees the same values as the Debug output: skipped fields are
+// left out, redacted fields are masked and custom formats are applied.
+// Flattened fields pass on the fields of the nested value, which has to
+// implement DebugFields too.
+
+use derive_debug::fmt::{DebugFields, FieldVisitor};
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+#[derive(CustomDebug)]
+#[debug(fields)]
+pub struct Peer {
+    addr: &'static str,
+}
+
+#[derive(CustomDebug)]
+#[debug(fields)]
+pub struct Login<T> {
+    user: String,
+    #[debug(redact)]
+    password: String,
+    #[debug(skip)]
+    attempts: u32,
+    #[debug = "0x{:02x}"]
+    flags: u8,
+    #[debug(flatten)]
+    peer: Peer,
+    extra: T,
+}
+
+#[derive(CustomDebug)]
+#[debug(fields)]
+pub enum Event {
+    Ping(u32),
+    Quit,
+}
+
+struct Pairs(Vec<String>);
+
+impl FieldVisitor for Pairs {
+    fn visit(&mut self, name: &'static str, value: &dyn Debug) {
+        self.0.push(format!("{}={:?}", name, value));
+    }
+}
+
+fn main() {
+    let login = Login {
+        user: "alice".to_owned(),
+        password: "hunter2".to_owned(),
+        attempts: 3,
+        flags: 10,
+        peer: Peer { addr: "10.0.0.1" },
+        extra: true,
+    };
+
+    let mut pairs = Pairs(Vec::new());
+    login.visit_fields(&mut pairs);
+    assert_eq!(
+        pairs.0,
+        [
+            "user=\"alice\"",
+            "password=***",
+            "flags=0x0a",
+            "addr=\"10.0.0.1\"",
+            "extra=true",
+        ],
+    );
+
+    // Closures are visitors too.
+    let mut names = Vec::new();
+    Event::Ping(5).visit_fields(&mut |name: &'static str, value: &dyn Debug| {
+        names.push(format!("{}={:?}", name, value));
+    });
+    Event::Quit.visit_fields(&mut |_: &'static str, _: &dyn Debug| unreachable!());
+    assert_eq!(names, ["0=5"]);
+}
@@ -0,0 +1,119 @@
+// Compact types like coordinates, ranges and versions read best on one line.
+// A format string on the struct, or on an enum variant, replaces the
+// `Name { .. }` output entirely. Its placeholders name fields just like those
+// of #[derive(CustomDisplay)], with any format spec, so `{x}` uses the
+// field's Display impl and `{x:?}` its Debug impl.
+//
+// Values computed from several fields go in `args`, each one an expression
+// that sees the named fields as references:
+//
+//     #[debug(fmt = "{start}..{end} ({len})", args(len = "end - start"))]
+//
+// Type parameters used by a placeholder are bounded on the trait it formats
+// with.
+//
+// Field attributes still apply: a placeholder of a `redact`, `hex` or `with`
+// field prints what the field would print in `Name { .. }`.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug("Point({x}, {y})")]
+pub struct Point<T> {
+    x: T,
+    y: T,
+}
+
+// Fields used with `{}` only need to implement Display.
+#[derive(CustomDebug)]
+#[debug("Label({text})")]
+pub struct Label {
+    text: NoDebug,
+}
+
+#[derive(CustomDebug)]
+#[debug(fmt = "{start}..{end} ({len} long)", args(len = "end - start"))]
+pub struct Span {
+    start: usize,
+    end: usize,
+}
+
+#[derive(CustomDebug)]
+#[debug("v{0}.{1}.{2}")]
+pub struct Version(u32, u32, u32);
+
+#[derive(CustomDebug)]
+pub enum Shape {
+    #[debug("Circle(r = {radius:.1})")]
+    Circle { radius: f64 },
+    #[debug(fmt = "Rect({w}x{h}, area {area})", args(area = "w * h"))]
+    Rect { w: u32, h: u32 },
+    Empty,
+}
+
+#[derive(CustomDebug)]
+#[debug("User({name}, {password})")]
+pub struct User {
+    name: String,
+    #[debug(redact)]
+    password: String,
+}
+
+#[derive(CustomDebug)]
+pub enum Request<T> {
+    #[debug("Login({user}, {token:?}, {flags})")]
+    Login {
+        user: String,
+        #[debug(redact = "len")]
+        token: String,
+        #[debug(hex)]
+        flags: T,
+    },
+}
+
+pub struct NoDebug;
+
+impl std::fmt::Display for NoDebug {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("nd")
+    }
+}
+
+fn main() {
+    assert_eq!(format!("{:?}", Point { x: 1, y: -2 }), "Point(1, -2)");
+    // Only Display is needed for `{x}`.
+    let point = Point {
+        x: NoDebug,
+        y: NoDebug,
+    };
+    assert_eq!(format!("{:?}", point), "Point(nd, nd)");
+    assert_eq!(format!("{:?}", Label { text: NoDebug }), "Label(nd)");
+
+    assert_eq!(
+        format!("{:?}", Span { start: 3, end: 10 }),
+        "3..10 (7 long)",
+    );
+    assert_eq!(format!("{:?}", Version(1, 2, 3)), "v1.2.3");
+
+    let shapes = vec![
+        Shape::Circle { radius: 1.25 },
+        Shape::Rect { w: 2, h: 3 },
+        Shape::Empty,
+    ];
+    assert_eq!(
+        format!("{:?}", shapes),
+        "[Circle(r = 1.2), Rect(2x3, area 6), Empty]",
+    );
+
+    let user = User {
+        name: "bob".to_owned(),
+        password: "hunter2".to_owned(),
+    };
+    assert_eq!(format!("{:?}", user), "User(bob, ***)");
+    let login = Request::Login {
+        user: "bob".to_owned(),
+        token: "secret".to_owned(),
+        flags: 255u8,
+    };
+    assert_eq!(format!("{:?}", login), "Login(bob, *** (len 6), 0xff)");
+}
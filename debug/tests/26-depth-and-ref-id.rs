@@ -0,0 +1,117 @@
+// Printing deep trees or graphs with shared nodes can run forever or produce
+// megabytes of output.
+//
+// #[debug(max_depth = N)] on a struct or enum cuts the output off after N
+// nested levels of that type, printing `…` in place of the deeper values. Each
+// type counts its own depth, per thread.
+//
+// #[debug(ref_id)] on a field holding an Rc or Arc, or an Option or Vec of
+// them, prints each shared target only once. The first time a target is seen
+// it is labelled `#1=`, any later occurrence is printed as `#1#`, like Common
+// Lisp's `*print-circle*`. This also ends cycles through RefCell. The labels
+// are numbered afresh each time the outermost value is formatted.
+
+use derive_debug::CustomDebug;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+#[derive(CustomDebug)]
+#[debug(max_depth = 2)]
+pub struct Tree {
+    value: u32,
+    children: Vec<Tree>,
+}
+
+#[derive(CustomDebug)]
+#[debug(max_depth = 10)]
+pub struct Outer {
+    inner: Inner,
+}
+
+#[derive(CustomDebug)]
+#[debug(max_depth = 1)]
+pub struct Inner {
+    x: u32,
+    next: Option<Box<Inner>>,
+}
+
+#[derive(CustomDebug)]
+pub struct Leaf {
+    name: &'static str,
+}
+
+#[derive(CustomDebug)]
+pub struct Graph {
+    #[debug(ref_id)]
+    nodes: Vec<Arc<Leaf>>,
+    #[debug(ref_id)]
+    root: Option<Arc<Leaf>>,
+}
+
+#[derive(CustomDebug)]
+pub struct Node {
+    id: u32,
+    #[debug(ref_id)]
+    next: Option<Rc<RefCell<Node>>>,
+}
+
+fn main() {
+    let tree = Tree {
+        value: 1,
+        children: vec![Tree {
+            value: 2,
+            children: vec![Tree {
+                value: 3,
+                children: vec![],
+            }],
+        }],
+    };
+    assert_eq!(
+        format!("{:?}", tree),
+        "Tree { value: 1, children: [Tree { value: 2, children: […] }] }",
+    );
+
+    // The limit of Inner is counted from the first Inner, not from Outer.
+    let outer = Outer {
+        inner: Inner {
+            x: 1,
+            next: Some(Box::new(Inner { x: 2, next: None })),
+        },
+    };
+    assert_eq!(
+        format!("{:?}", outer),
+        "Outer { inner: Inner { x: 1, next: Some(…) } }",
+    );
+
+    let a = Arc::new(Leaf { name: "a" });
+    let b = Arc::new(Leaf { name: "b" });
+    let graph = Graph {
+        nodes: vec![a.clone(), b, a.clone()],
+        root: Some(a),
+    };
+    let expected = "Graph { nodes: [#1=Leaf { name: \"a\" }, #2=Leaf { name: \"b\" }, #1#], \
+                    root: Some(#1#) }";
+    assert_eq!(format!("{:?}", graph), expected);
+    // Labels start over for the next top-level value.
+    assert_eq!(format!("{:?}", graph), expected);
+
+    let first = Rc::new(RefCell::new(Node { id: 1, next: None }));
+    let second = Rc::new(RefCell::new(Node {
+        id: 2,
+        next: Some(first.clone()),
+    }));
+    first.borrow_mut().next = Some(second);
+    let node = Node {
+        id: 0,
+        next: Some(first.clone()),
+    };
+    assert_eq!(
+        format!("{:?}", node),
+        "Node { id: 0, next: Some(#1=RefCell { value: Node { id: 1, next: \
+         Some(#2=RefCell { value: Node { id: 2, next: Some(#1#) } }) } }) }",
+    );
+
+    // Break the cycle so the nodes are freed.
+    first.borrow_mut().next = None;
+}
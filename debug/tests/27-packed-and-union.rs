@@ -0,0 +1,60 @@
+// References to the fields of a #[repr(packed)] struct may be unaligned, so
+// the compiler rejects the `&self.field` borrows that the derive normally
+// generates. For packed structs every field is copied into a temporary
+// first, which requires the fields to be Copy.
+//
+// Unions can't know which of their fields holds the value. They are printed
+// as `Name { .. }` unless #[debug(unsafe_union_as = "field")] names the field
+// to format. Debug::fmt is safe to call, so the attribute promises that
+// reading that field is always sound. That holds when every bit pattern of the
+// union is a valid value of the field's type, like `u32` below, but not for
+// `bool`, references or enums, where formatting the union would be undefined
+// behavior.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[repr(C, packed)]
+pub struct Header {
+    tag: u8,
+    #[debug = "0x{:08x}"]
+    len: u32,
+    #[debug(skip)]
+    _reserved: [u8; 3],
+}
+
+#[derive(CustomDebug)]
+#[repr(packed(2))]
+pub struct Pair(u8, u64);
+
+#[derive(CustomDebug)]
+#[debug(unsafe_union_as = "bits")]
+pub union Word {
+    bits: u32,
+    float: f32,
+}
+
+#[derive(CustomDebug)]
+pub union Opaque {
+    int: u64,
+    ptr: *const u8,
+}
+
+fn main() {
+    let header = Header {
+        tag: 7,
+        len: 255,
+        _reserved: [0; 3],
+    };
+    assert_eq!(
+        format!("{:?}", header),
+        "Header { tag: 7, len: 0x000000ff, .. }",
+    );
+    assert_eq!(format!("{:?}", Pair(1, 2)), "Pair(1, 2)");
+
+    let word = Word { float: 1.0 };
+    assert_eq!(format!("{:?}", word), "Word { bits: 1065353216 }");
+
+    let opaque = Opaque { int: 0 };
+    assert_eq!(format!("{:?}", opaque), "Opaque { .. }");
+}
@@ -0,0 +1,13 @@
+// Fields of packed structs that aren't Copy can't be formatted, the error
+// points at the field's type.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[repr(packed)]
+pub struct Record {
+    id: u32,
+    name: String,
+}
+
+fn main() {}
@@ -0,0 +1,48 @@
+// Mistakes in the #[debug] attributes are reported with a span pointing at
+// them instead of a panic inside the macro or being silently ignored. All of
+// them are reported at once.
+//
+// The format strings of #[debug = "..."] are given only the field, so they
+// are checked for placeholders that would need more arguments.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(bound = "T Debug")]
+pub struct Settings<T> {
+    #[debug(skp)]
+    name: String,
+    #[debug = "{} of {}"]
+    count: u32,
+    #[debug = "{value:?}"]
+    value: T,
+    #[debug = "plain"]
+    flags: u8,
+    #[debug(skip = "yes", flatten(name))]
+    extra: u8,
+    #[debug(hex, bitflags = "Mode")]
+    mode: u8,
+}
+
+// `transparent` only applies to structs and `unsafe_union_as` to unions.
+#[derive(CustomDebug)]
+#[debug(transparent, unsafe_union_as = "x")]
+pub enum Choice {
+    X(u8),
+}
+
+#[derive(CustomDebug)]
+#[debug(unsafe_union_as = "nope")]
+pub struct Plain {
+    nope: u8,
+}
+
+// A format string can't print a field that is left out.
+#[derive(CustomDebug)]
+#[debug("Secret({key})")]
+pub struct Secret {
+    #[debug(skip)]
+    key: String,
+}
+
+fn main() {}
@@ -0,0 +1,63 @@
+// PhantomData and PhantomPinned fields are recognized by their name when
+// imported, or by their full path in std::marker or core::marker. They never
+// add a bound, even for patterns like PhantomData<fn() -> T>, and are printed
+// compactly as the type is written, like `PhantomData<T>`. Types from other
+// modules that happen to share the name are formatted as usual.
+//
+// Aliases of marker types can't be recognized from the field's type, mark
+// those fields #[debug(phantom)]. #[debug(no_bound)] keeps a field from adding
+// a bound without changing how it is printed, for types that are Debug
+// whatever their parameters are.
+
+use derive_debug::CustomDebug;
+use std::fmt::{self, Debug};
+use std::marker::{PhantomData, PhantomPinned};
+
+mod fake {
+    #[derive(Debug)]
+    pub struct PhantomData<T>(pub T);
+}
+
+type Tag<T> = PhantomData<T>;
+
+pub struct Id<T>(u32, PhantomData<T>);
+
+impl<T> Debug for Id<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+#[derive(CustomDebug)]
+pub struct Handle<T, U, V, W> {
+    marker: PhantomData<T>,
+    pinned: PhantomPinned,
+    func: core::marker::PhantomData<fn() -> U>,
+    #[debug(phantom)]
+    tag: Tag<V>,
+    #[debug(no_bound)]
+    id: Id<V>,
+    fake: fake::PhantomData<W>,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+
+    assert_debug::<Handle<NotDebug, NotDebug, NotDebug, u8>>();
+
+    let handle: Handle<NotDebug, NotDebug, NotDebug, u8> = Handle {
+        marker: PhantomData,
+        pinned: PhantomPinned,
+        func: PhantomData,
+        tag: PhantomData,
+        id: Id(7, PhantomData),
+        fake: fake::PhantomData(1),
+    };
+    assert_eq!(
+        format!("{:?}", handle),
+        "Handle { marker: PhantomData<T>, pinned: PhantomPinned, \
+         func: PhantomData<fn() -> U>, tag: Tag<V>, id: #7, fake: PhantomData(1) }",
+    );
+}
@@ -0,0 +1,55 @@
+// The generated impls refer to everything through absolute paths into `core`
+// and this crate, so they keep working when the caller's code gives prelude
+// names, or `std` and `fmt` themselves, a different meaning.
+
+#![allow(dead_code, non_camel_case_types, non_upper_case_globals, unused_macros)]
+
+use derive_debug::{CustomDebug, CustomDisplay};
+
+type Option = ();
+type Some = ();
+type None = ();
+type Result = ();
+type Box = ();
+mod std {}
+mod fmt {}
+const fmt: () = ();
+macro_rules! format_args {
+    ($($tt:tt)*) => {
+        compile_error!("local format_args! used")
+    };
+}
+
+#[derive(CustomDebug)]
+#[debug(skip_none)]
+pub struct Field {
+    name: &'static str,
+    #[debug = "0b{:08b}"]
+    bitmask: u8,
+    #[debug(redact)]
+    secret: u32,
+    port: ::core::option::Option<u16>,
+}
+
+#[derive(CustomDebug, CustomDisplay)]
+pub enum Level {
+    #[display("low ({0})")]
+    Low(u8),
+    #[debug("High!")]
+    #[display("high")]
+    High,
+}
+
+fn main() {
+    let field = Field {
+        name: "F",
+        bitmask: 0b0011_0011,
+        secret: 1,
+        port: ::core::option::Option::None,
+    };
+    assert_eq!(
+        ::std::format!("{:?}", field),
+        r#"Field { name: "F", bitmask: 0b00110011, secret: *** }"#,
+    );
+    assert_eq!(::std::format!("{:?} {}", Level::High, Level::Low(1)), "High! low (1)");
+}
@@ -0,0 +1,48 @@
+// The derives also work in `no_std` crates. Turn off the default `std`
+// feature of derive_debug to use them without std, or keep only `alloc` for
+// the impls on String, Vec and Box. #[debug(max_depth)] and #[debug(ref_id)]
+// rely on thread-locals and need `std`.
+//
+// std is still linked here so that this test can run as a normal binary, but
+// under a different name so that the generated code can't refer to `::std`.
+
+#![no_std]
+
+extern crate alloc;
+extern crate std as host_std;
+
+use alloc::format;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use derive_debug::{CustomDebug, CustomDisplay};
+
+#[derive(CustomDebug)]
+pub struct Packet<T> {
+    id: u16,
+    #[debug(with = "hex_bytes")]
+    payload: Vec<u8>,
+    #[debug(truncate = 2)]
+    history: [u8; 4],
+    marker: PhantomData<T>,
+}
+
+#[derive(CustomDebug, CustomDisplay)]
+#[display("{0}.{1}")]
+#[debug("v{0}.{1}")]
+pub struct Version(u8, u8);
+
+fn main() {
+    struct NotDebug;
+
+    let packet: Packet<NotDebug> = Packet {
+        id: 7,
+        payload: alloc::vec![0xde, 0xad],
+        history: [1, 2, 3, 4],
+        marker: PhantomData,
+    };
+    assert_eq!(
+        format!("{:?}", packet),
+        "Packet { id: 7, payload: dead, history: [1, 2, … (2 more)], marker: PhantomData<T> }",
+    );
+    assert_eq!(format!("{} {:?}", Version(1, 2), Version(1, 2)), "1.2 v1.2");
+}
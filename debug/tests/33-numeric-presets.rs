@@ -0,0 +1,113 @@
+// Register dumps and digests are unreadable as decimal arrays. A field marked
+// #[debug(hex)], #[debug(bin)] or #[debug(octal)] prints its integer, or every
+// integer of a Vec, array or slice, in that base. #[debug(hex_bytes)] prints
+// bytes as one hex string instead.
+//
+// #[debug(bitflags = "Flags")] prints an integer through the `bitflags` type
+// holding its bits, using its `from_bits_truncate` constructor.
+//
+// Type parameters of a preset field are bounded on what the preset needs,
+// `derive_debug::fmt::RadixDebug` for the bases and `AsRef<[u8]>` for
+// hex_bytes, instead of Debug.
+
+use derive_debug::CustomDebug;
+use std::fmt::{self, Debug};
+
+// What the `bitflags!` macro generates, as far as this test is concerned.
+pub struct Mode(u8);
+
+impl Mode {
+    pub fn from_bits_truncate(bits: u8) -> Self {
+        Mode(bits & 0b11)
+    }
+}
+
+impl Debug for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = [(0b01, "READ"), (0b10, "WRITE")];
+        let set: Vec<_> = names.iter().filter(|(bit, _)| self.0 & bit != 0).collect();
+        write!(f, "Mode(")?;
+        for (i, (_, name)) in set.iter().enumerate() {
+            if i > 0 {
+                write!(f, " | ")?;
+            }
+            write!(f, "{}", name)?;
+        }
+        write!(f, ")")
+    }
+}
+
+#[derive(CustomDebug)]
+pub struct Registers<'a> {
+    #[debug(hex)]
+    status: u32,
+    #[debug(hex)]
+    dump: Vec<u8>,
+    #[debug(bin)]
+    mask: [u8; 2],
+    #[debug(octal)]
+    perms: &'a [u16],
+    #[debug(hex_bytes)]
+    digest: [u8; 4],
+    #[debug(hex_bytes)]
+    key: &'a [u8],
+    #[debug(bitflags = "Mode")]
+    mode: u8,
+}
+
+#[derive(CustomDebug)]
+pub enum Reading {
+    Raw(#[debug(hex)] Option<u16>),
+}
+
+// The base is applied to the elements, not to `T`, so no `T: Debug` bound.
+pub struct NotDebug;
+
+#[derive(CustomDebug)]
+pub struct Wrapper<T> {
+    #[debug(hex)]
+    value: u8,
+    #[debug(skip)]
+    _marker: Option<T>,
+}
+
+#[derive(CustomDebug)]
+pub struct Gen<T, B> {
+    #[debug(hex)]
+    x: T,
+    #[debug(hex_bytes)]
+    bytes: B,
+}
+
+fn main() {
+    let registers = Registers {
+        status: 0xdead_beef,
+        dump: vec![0x01, 0xff],
+        mask: [0b101, 0],
+        perms: &[0o644, 0o755],
+        digest: [0xca, 0xfe, 0xba, 0xbe],
+        key: &[0x00, 0x0f],
+        mode: 0b111,
+    };
+
+    assert_eq!(
+        format!("{:?}", registers),
+        "Registers { status: 0xdeadbeef, dump: [0x1, 0xff], mask: [0b101, 0b0], \
+         perms: [0o644, 0o755], digest: cafebabe, key: 000f, mode: Mode(READ | WRITE) }",
+    );
+
+    assert_eq!(format!("{:?}", Reading::Raw(Some(0x2a))), "Raw(Some(0x2a))");
+    assert_eq!(format!("{:?}", Reading::Raw(None)), "Raw(None)");
+
+    let wrapper = Wrapper::<NotDebug> {
+        value: 0x10,
+        _marker: None,
+    };
+    assert_eq!(format!("{:?}", wrapper), "Wrapper { value: 0x10, .. }");
+
+    let gen = Gen {
+        x: [0x0au16, 0xff],
+        bytes: vec![0xab, 0x01],
+    };
+    assert_eq!(format!("{:?}", gen), "Gen { x: [0xa, 0xff], bytes: ab01 }");
+}
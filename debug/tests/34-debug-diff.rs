@@ -0,0 +1,92 @@
+// assert_eq! on large structs prints both values in full, leaving the reader
+// to find what differs. #[derive(DebugDiff)] implements
+// `fn debug_diff(&self, other: &Self) -> Diff`, listing only the fields whose
+// Debug output differs. Fields of types that derive DebugDiff too are compared
+// field by field, with the path to each change joined by `.`.
+//
+// Fields are compared by what they print, so #[debug(skip)] fields are left
+// out and the custom formats and redaction of CustomDebug apply. Different
+// variants of an enum are reported as a change of the whole value.
+
+use derive_debug::{CustomDebug, DebugDiff};
+
+#[derive(CustomDebug, DebugDiff, Clone)]
+pub struct Limits {
+    max_connections: u32,
+    #[debug = "{}ms"]
+    timeout: u64,
+}
+
+#[derive(CustomDebug, DebugDiff, Clone)]
+pub enum Mode {
+    Active { since: u32 },
+    Disabled,
+}
+
+#[derive(CustomDebug, DebugDiff, Clone)]
+pub struct Config {
+    name: String,
+    port: u16,
+    limits: Limits,
+    mode: Mode,
+    tags: Vec<&'static str>,
+    #[debug(redact)]
+    password: String,
+    #[debug(skip)]
+    generation: u64,
+    #[debug(rename = "peer")]
+    address: (u8, u8),
+}
+
+#[derive(CustomDebug, DebugDiff)]
+pub struct Pair<T>(T, T);
+
+fn main() {
+    let config = Config {
+        name: "primary".to_owned(),
+        port: 80,
+        limits: Limits {
+            max_connections: 100,
+            timeout: 30,
+        },
+        mode: Mode::Active { since: 1 },
+        tags: vec!["a"],
+        password: "hunter2".to_owned(),
+        generation: 1,
+        address: (10, 0),
+    };
+    assert!(config.debug_diff(&config.clone()).is_empty());
+
+    let mut other = config.clone();
+    other.port = 8080;
+    other.limits.timeout = 45;
+    other.mode = Mode::Active { since: 2 };
+    other.password = "secret".to_owned();
+    other.generation = 2;
+    other.address = (10, 1);
+    let diff = config.debug_diff(&other);
+    assert_eq!(
+        diff.to_string(),
+        "port: 80 != 8080\n\
+         limits.timeout: 30ms != 45ms\n\
+         mode.since: 1 != 2\n\
+         peer: (10, 0) != (10, 1)",
+    );
+    assert_eq!(diff.changes()[0].path, "port");
+    assert_eq!(diff.changes()[0].left, "80");
+    assert_eq!(diff.changes()[0].right, "8080");
+
+    other.mode = Mode::Disabled;
+    other.tags.push("b");
+    let diff = config.debug_diff(&other);
+    assert_eq!(diff.changes()[2].path, "mode");
+    assert_eq!(diff.changes()[2].left, "Active { since: 1 }");
+    assert_eq!(diff.changes()[2].right, "Disabled");
+    assert_eq!(diff.changes()[3].left, "[\"a\"]");
+    assert_eq!(diff.changes()[3].right, "[\"a\", \"b\"]");
+
+    assert_eq!(
+        Pair("x", "y").debug_diff(&Pair("x", "z")).to_string(),
+        "1: \"y\" != \"z\"",
+    );
+}
@@ -9,4 +9,30 @@ fn tests() {
     t.pass("tests/06-bound-trouble.rs");
     t.pass("tests/07-associated-type.rs");
     t.pass("tests/08-escape-hatch.rs");
+    t.pass("tests/09-enum.rs");
+    t.pass("tests/10-tuple-and-unit-struct.rs");
+    t.compile_fail("tests/11-transparent-wrong.rs");
+    t.pass("tests/12-skip-and-redact.rs");
+    t.pass("tests/13-custom-formatter.rs");
+    t.pass("tests/14-field-bound.rs");
+    t.pass("tests/15-bound-inference.rs");
+    t.pass("tests/16-lifetime-and-const-generics.rs");
+    t.pass("tests/17-rename.rs");
+    t.compile_fail("tests/18-rename-all-wrong.rs");
+    t.pass("tests/19-display.rs");
+    t.compile_fail("tests/20-display-unknown-field.rs");
+    t.pass("tests/21-skip-if.rs");
+    t.pass("tests/22-truncate.rs");
+    t.pass("tests/23-flatten.rs");
+    t.pass("tests/24-debug-fields.rs");
+    t.pass("tests/25-format-string.rs");
+    t.pass("tests/26-depth-and-ref-id.rs");
+    t.pass("tests/27-packed-and-union.rs");
+    t.compile_fail("tests/28-packed-not-copy.rs");
+    t.compile_fail("tests/29-attr-errors.rs");
+    t.pass("tests/30-phantom.rs");
+    t.pass("tests/31-redefined-prelude-types.rs");
+    t.pass("tests/32-no-std.rs");
+    t.pass("tests/33-numeric-presets.rs");
+    t.pass("tests/34-debug-diff.rs");
 }
@@ -0,0 +1,56 @@
+// Both derive_builder_impl and derive_debug_impl print field types the way
+// they are written in the source, the first for the `FIELDS` of a builder and
+// the second for `#[debug(len_only)]` and marker fields. Crates with the
+// "proc-macro" crate type can only export macros, so the function they share
+// lives in this crate.
+
+use quote::quote;
+
+// `quote!(#ty).to_string()` puts spaces between every token, turn that back
+// into the type as it would usually be written, e.g. `Vec<String>`. The spaces
+// around `->` and after a lifetime or keyword stay: `fn(u8) -> u8`,
+// `&'static [u8]`, `&mut [u8]`.
+pub fn type_name(ty: &syn::Type) -> String {
+    let tokens = quote!(#ty).to_string();
+    let chars = tokens.chars().collect::<Vec<_>>();
+    let is_tight = |i: usize| {
+        let c = chars[i];
+        let arrow = c == '>' && i > 0 && chars[i - 1] == '-';
+        !arrow && "<>:&()[]".contains(c)
+    };
+    // Whether the token ending at `i` is a lifetime like `'a`, or a keyword
+    // like `mut` that is followed by a space even before `[` or `(`.
+    let word_before = |i: usize| {
+        let len = chars[..=i]
+            .iter()
+            .rev()
+            .take_while(|c| c.is_alphanumeric() || **c == '_')
+            .count();
+        &chars[i + 1 - len..=i]
+    };
+    let is_lifetime = |i: usize| {
+        let len = word_before(i).len();
+        len > 0 && i >= len && chars[i - len] == '\''
+    };
+    let is_keyword = |i: usize| {
+        let word = word_before(i).iter().collect::<String>();
+        ["mut", "const", "dyn", "impl"].contains(&word.as_str()) && !is_lifetime(i)
+    };
+
+    let mut name = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c == ' ' {
+            let next = chars[i + 1];
+            let keep = chars[i + 1..].starts_with(&['-', '>'])
+                || (is_lifetime(i - 1) && !">,;)]".contains(next))
+                || is_keyword(i - 1);
+            let before = is_tight(i - 1) || chars[i - 1] == '*';
+            let after = is_tight(i + 1) || ",;".contains(next);
+            if !keep && (before || after) {
+                continue;
+            }
+        }
+        name.push(c);
+    }
+    name
+}